@@ -0,0 +1,493 @@
+use std::io::Read;
+
+use itertools::Itertools;
+use rustyms::AminoAcid;
+use serde::Deserialize;
+
+use crate::{
+    error::GenerateError,
+    fasta::{c_region, d_regions, j_region, v_region, GeneInfo, Translated},
+    shared::*,
+    translate, Release, SingleSeq,
+};
+
+/// An AIRR germline set file, either a single germline set or a file with a list of germline sets
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum AirrFile {
+    Sets {
+        #[serde(rename = "GermlineSet")]
+        germline_sets: Vec<GermlineSet>,
+    },
+    Set(GermlineSet),
+}
+
+/// A germline set in the AIRR schema, only the fields needed are parsed
+#[derive(Deserialize)]
+struct GermlineSet {
+    #[serde(default)]
+    species: Option<Ontology>,
+    /// The version of this germline set, a number in the schema but sometimes given as text
+    #[serde(default)]
+    release_version: Option<serde_json::Value>,
+    #[serde(default)]
+    release_date: Option<String>,
+    allele_descriptions: Vec<AlleleDescription>,
+}
+
+#[derive(Deserialize)]
+struct Ontology {
+    label: Option<String>,
+}
+
+/// A single allele in the AIRR schema, all positions are 1-based
+#[derive(Deserialize)]
+struct AlleleDescription {
+    allele_description_id: String,
+    label: Option<String>,
+    sequence: String,
+    #[serde(default)]
+    coding_sequence: Option<String>,
+    sequence_type: String,
+    #[serde(default)]
+    functional: Option<bool>,
+    /// How this allele was inferred, eg `Rearranged only` or `Genomic and rearranged`
+    #[serde(default)]
+    inference_type: Option<String>,
+    #[serde(default)]
+    locus: Option<String>,
+    #[serde(default)]
+    subgroup_designation: Option<String>,
+    #[serde(default)]
+    gene_designation: Option<String>,
+    #[serde(default)]
+    allele_designation: Option<String>,
+    #[serde(default)]
+    species: Option<Ontology>,
+    #[serde(default)]
+    v_gene_delineations: Option<Vec<Delineation>>,
+    /// The codon position (1, 2, or 3) of the first nucleotide of the coding sequence of a J gene
+    #[serde(default)]
+    j_codon_frame: Option<usize>,
+    /// The position of the last nucleotide of the CDR3 in the coding sequence of a J gene
+    #[serde(default)]
+    j_cdr3_end: Option<usize>,
+}
+
+/// The delineation of the regions of a V gene, the positions are in the unaligned sequence
+#[derive(Deserialize)]
+struct Delineation {
+    #[serde(default)]
+    delineation_scheme: Option<String>,
+    #[serde(default)]
+    unaligned_sequence: Option<String>,
+    /// The IMGT gapped sequence if this delineation uses the IMGT scheme
+    #[serde(default)]
+    aligned_sequence: Option<String>,
+    fwr1_start: Option<usize>,
+    fwr1_stop: Option<usize>,
+    cdr1_start: Option<usize>,
+    cdr1_stop: Option<usize>,
+    fwr2_start: Option<usize>,
+    fwr2_stop: Option<usize>,
+    cdr2_start: Option<usize>,
+    cdr2_stop: Option<usize>,
+    fwr3_start: Option<usize>,
+    fwr3_stop: Option<usize>,
+    cdr3_start: Option<usize>,
+}
+
+/// The error for an allele, with the species (if known) and the identifier and label of the allele
+type AlleleError = (Option<Species>, String, GenerateError);
+
+/// Parse an AIRR Germline Set JSON file (eg from OGRDB). The AIRR allele labels are parsed as IMGT style
+/// names (eg `IGHV1-2*02`), if that fails (eg for novel alleles `IGHV1-2*02_S4953`) the name is built from
+/// the locus and the subgroup, gene, and allele designations. The labels are kept in the provenance as
+/// well as the allele description identifiers.
+/// The release version and date of the germline set(s) are stored in `release`.
+/// # Errors
+/// If the file is not a valid AIRR germline set.
+pub fn parse_airr<T: Read>(
+    reader: T,
+    release: &mut Option<Release>,
+) -> Result<impl Iterator<Item = Result<Vec<(Species, SingleSeq)>, AlleleError>>, String> {
+    let sets = match serde_json::from_reader(reader)
+        .map_err(|e| format!("Invalid AIRR germline set: {e}"))?
+    {
+        AirrFile::Sets { germline_sets } => germline_sets,
+        AirrFile::Set(set) => vec![set],
+    };
+    let versions = sets
+        .iter()
+        .filter_map(|set| match set.release_version.as_ref()? {
+            serde_json::Value::String(version) => Some(version.clone()),
+            version => Some(version.to_string()),
+        })
+        .unique()
+        .join(", ");
+    let dates = sets
+        .iter()
+        .filter_map(|set| set.release_date.clone())
+        .unique()
+        .join(", ");
+    if !versions.is_empty() || !dates.is_empty() {
+        let unknown = Release::default();
+        *release = Some(Release {
+            release: if versions.is_empty() {
+                unknown.release
+            } else {
+                versions
+            },
+            date: if dates.is_empty() {
+                unknown.date
+            } else {
+                dates
+            },
+        });
+    }
+    Ok(sets.into_iter().flat_map(|set| {
+        let species = set.species;
+        set.allele_descriptions
+            .into_iter()
+            .map(move |allele| parse_allele(allele, species.as_ref()))
+    }))
+}
+
+fn parse_allele(
+    allele: AlleleDescription,
+    set_species: Option<&Ontology>,
+) -> Result<Vec<(Species, SingleSeq)>, AlleleError> {
+    let label = allele.label.clone().unwrap_or_default();
+    let id = format!("{}|{label}", allele.allele_description_id);
+    let species = allele
+        .species
+        .as_ref()
+        .or(set_species)
+        .and_then(|s| s.label.as_deref())
+        .and_then(|s| s.parse::<Species>().ok())
+        .ok_or_else(|| (None, id.clone(), GenerateError::MissingSpecies))?;
+    let error = |err| (Some(species), id.clone(), err);
+    let (name, allele_number) = Gene::from_imgt_name_with_allele(&label)
+        .or_else(|e| designated_name(&allele).ok_or(e))
+        .map_err(|e| error(GenerateError::NameParse(e)))?;
+    let info = GeneInfo {
+        species,
+        name,
+        allele: allele_number,
+        acc: allele.allele_description_id.clone(),
+        label: Some(label),
+        functionality: functionality(&allele),
+        truncation: Truncation::default(),
+    };
+    let sequence = allele.sequence.to_ascii_lowercase();
+    let coding = allele
+        .coding_sequence
+        .as_deref()
+        .map_or_else(|| sequence.clone(), str::to_ascii_lowercase);
+
+    match allele.sequence_type.as_str() {
+        "V" => {
+            let delineation = allele.v_gene_delineations.as_ref().and_then(|d| {
+                d.iter()
+                    .find(|d| d.delineation_scheme.as_deref() == Some("IMGT"))
+                    .or_else(|| d.first())
+            });
+            let translated = match delineation {
+                Some(Delineation {
+                    aligned_sequence: Some(aligned),
+                    delineation_scheme: Some(scheme),
+                    ..
+                }) if scheme == "IMGT" => v_region(&aligned.to_ascii_lowercase()),
+                Some(delineation) => delineated_v_region(
+                    &delineation
+                        .unaligned_sequence
+                        .as_deref()
+                        .map_or_else(|| coding.clone(), str::to_ascii_lowercase),
+                    delineation,
+                ),
+                None => Err(GenerateError::MissingRegion(
+                    "V gene delineation".to_string(),
+                )),
+            }
+            .map_err(error)?;
+            Ok(vec![info.single_seq(translated, None, None)])
+        }
+        "D" => Ok(d_regions(&coding)
+            .map_err(error)?
+            .into_iter()
+            .map(|(frame, translated)| info.single_seq(translated, Some(frame), None))
+            .collect()),
+        "J" => {
+            // Skip the nucleotides before the first full codon, for frame 2 the first codon starts at the third nucleotide
+            let shift = (4 - allele.j_codon_frame.unwrap_or(1).clamp(1, 3)) % 3;
+            let boundary = allele
+                .j_cdr3_end
+                .and_then(|end| end.checked_sub(shift))
+                .map(|offset| offset / 3);
+            Ok(vec![info.single_seq(
+                j_region(coding.get(shift..).unwrap_or_default(), boundary).map_err(error)?,
+                None,
+                None,
+            )])
+        }
+        "C" => Ok(vec![info.single_seq(
+            c_region(&coding, info.name.chain).map_err(error)?,
+            None,
+            Some(Isoform::Secreted),
+        )]),
+        other => Err(error(GenerateError::UnsupportedLabel(format!(
+            "sequence type {other}"
+        )))),
+    }
+}
+
+/// Build the name of an allele from its designations, eg locus `IGH`, sequence type `V`, subgroup `1`,
+/// gene `2`, and allele `02_S4953` give `IGHV1-2*02`. Any text following the allele number is only kept in the label.
+fn designated_name(allele: &AlleleDescription) -> Option<(Gene, usize)> {
+    let locus = allele.locus.as_deref()?;
+    let gene = allele.gene_designation.as_deref()?;
+    let kind = &allele.sequence_type;
+    let name = allele.subgroup_designation.as_deref().map_or_else(
+        || format!("{locus}{kind}{gene}"),
+        |subgroup| format!("{locus}{kind}{subgroup}-{gene}"),
+    );
+    let number = allele
+        .allele_designation
+        .as_deref()
+        .map(|a| {
+            a.chars()
+                .take_while(char::is_ascii_digit)
+                .collect::<String>()
+        })
+        .and_then(|n| n.parse().ok())
+        .unwrap_or(1);
+    Gene::from_imgt_name(&name).ok().map(|gene| (gene, number))
+}
+
+/// The functionality of an allele. AIRR does not distinguish ORFs and pseudogenes, so non functional alleles
+/// are unknown. Alleles without annotation are functional if they were seen rearranged.
+fn functionality(allele: &AlleleDescription) -> Functionality {
+    let rearranged = allele
+        .inference_type
+        .as_deref()
+        .is_some_and(|t| t.to_ascii_lowercase().contains("rearranged"));
+    if allele.functional == Some(true) || (allele.functional.is_none() && rearranged) {
+        Functionality::Functional
+    } else {
+        Functionality::Unknown
+    }
+}
+
+/// Translate an unaligned V gene with the regions as delineated. The conserved residues are placed
+/// following the IMGT unique numbering: Cys23 is the fourth to last residue of FR1, Trp41 the third
+/// residue of FR2, and Cys104 the last residue of FR3.
+fn delineated_v_region(
+    sequence: &str,
+    delineation: &Delineation,
+) -> Result<Translated, GenerateError> {
+    let bounds = [
+        (
+            Region::FR1,
+            "FWR1",
+            delineation.fwr1_start,
+            delineation.fwr1_stop,
+        ),
+        (
+            Region::CDR1,
+            "CDR1",
+            delineation.cdr1_start,
+            delineation.cdr1_stop,
+        ),
+        (
+            Region::FR2,
+            "FWR2",
+            delineation.fwr2_start,
+            delineation.fwr2_stop,
+        ),
+        (
+            Region::CDR2,
+            "CDR2",
+            delineation.cdr2_start,
+            delineation.cdr2_stop,
+        ),
+        (
+            Region::FR3,
+            "FWR3",
+            delineation.fwr3_start,
+            delineation.fwr3_stop,
+        ),
+        (
+            Region::CDR3,
+            "CDR3",
+            delineation.cdr3_start,
+            Some(sequence.len()),
+        ),
+    ];
+    let mut aa = Vec::new();
+    let mut regions = Vec::new();
+    let mut dna = String::new();
+    let mut dna_regions = Vec::new();
+    for (region, name, start, stop) in bounds {
+        let (Some(start), Some(stop)) = (start, stop) else {
+            return Err(GenerateError::MissingRegion(name.to_string()));
+        };
+        let part = sequence
+            .get(start.saturating_sub(1)..stop.min(sequence.len()))
+            .ok_or(GenerateError::OutOfRange("Delineation outside of sequence"))?;
+        let (part, translated) = translate(part)?;
+        regions.push((region, translated.len()));
+        dna_regions.push((region, part.len()));
+        aa.extend(translated);
+        dna.push_str(part);
+    }
+    let end = |index: usize| regions[..=index].iter().map(|r| r.1).sum::<usize>();
+    let conserved = [
+        (Annotation::Cysteine1, end(0).checked_sub(4), AminoAcid::C),
+        (Annotation::Tryptophan, Some(end(1) + 2), AminoAcid::W),
+        (Annotation::Cysteine2, end(4).checked_sub(1), AminoAcid::C),
+    ];
+    let annotations = conserved
+        .into_iter()
+        .filter_map(|(annotation, index, expected)| {
+            index
+                .filter(|index| aa.get(*index) == Some(&expected))
+                .map(|index| (annotation, index))
+        })
+        .collect();
+    Ok((aa, regions, annotations, dna, dna_regions))
+}
+
+#[cfg(test)]
+#[allow(clippy::missing_panics_doc)]
+mod tests {
+    use super::*;
+
+    type Alleles = Vec<Result<Vec<(Species, SingleSeq)>, AlleleError>>;
+
+    /// Parse a germline set with the given allele descriptions (as JSON objects)
+    fn parse(alleles: &[String]) -> (Option<Release>, Alleles) {
+        let json = format!(
+            r#"{{"species": {{"label": "Homo sapiens"}}, "release_version": 3, "release_date": "2024-01-01", "allele_descriptions": [{}]}}"#,
+            alleles.join(",")
+        );
+        let mut release = None;
+        let alleles = parse_airr(json.as_bytes(), &mut release).unwrap().collect();
+        (release, alleles)
+    }
+
+    fn allele(label: &str, sequence_type: &str, coding: &str, extra: &str) -> String {
+        format!(
+            r#"{{"allele_description_id": "OGRDB:{label}", "label": "{label}", "sequence": "gggg{coding}tttt", "coding_sequence": "{coding}", "sequence_type": "{sequence_type}"{extra}}}"#
+        )
+    }
+
+    #[test]
+    fn germline_set() {
+        let v = "caggtgcagctggtgcagtctggggctgaggtgaagaagcctggggcctcagtgaaggtctcctgcaaggcttctggatacaccttcaccggctactatatgcactgggtgcgacaggcccctggacaagggcttgagtggatgggacggatcaaccctaacagtggtggcacaaactatgcacagaagtttcagggcagggtcaccagtaccagggacacgtccatcagcacagcctacatggagctgagcaggctgagatctgacgacacggccgtgtattactgtgcgagaga";
+        let j = "tactttgactactggggccagggaaccctggtcaccgtctcctcag";
+        let (release, alleles) = parse(&[
+            allele(
+                "IGHV1-2*01",
+                "V",
+                v,
+                r#", "inference_type": "Genomic and rearranged", "v_gene_delineations": [{"delineation_scheme": "Chothia", "fwr1_start": 1, "fwr1_stop": 75, "cdr1_start": 76, "cdr1_stop": 99, "fwr2_start": 100, "fwr2_stop": 150, "cdr2_start": 151, "cdr2_stop": 174, "fwr3_start": 175, "fwr3_stop": 288, "cdr3_start": 289}]"#,
+            ),
+            allele("IGHD1-1*01", "D", "ggtacaactggaacgac", ""),
+            allele(
+                "IGHJ4*01",
+                "J",
+                j,
+                r#", "j_codon_frame": 1, "j_cdr3_end": 12"#,
+            ),
+            allele(
+                "IGHJ4*02",
+                "J",
+                &format!("ac{j}"),
+                r#", "j_codon_frame": 2, "j_cdr3_end": 14"#,
+            ),
+            allele(
+                "IGHJ4*03",
+                "J",
+                &format!("c{j}"),
+                r#", "j_codon_frame": 3, "j_cdr3_end": 13"#,
+            ),
+            allele("IGKC*01", "C", "cgaactgtggctgcaccatctgtc", ""),
+            allele("IGHV1-3*01", "V", v, ""),
+            allele(
+                "IGHJ4*02_S1234",
+                "J",
+                j,
+                r#", "locus": "IGH", "gene_designation": "4", "allele_designation": "02_S1234", "functional": false, "j_codon_frame": 1, "j_cdr3_end": 12"#,
+            ),
+        ]);
+        assert_eq!(
+            release,
+            Some(Release {
+                release: "3".to_string(),
+                date: "2024-01-01".to_string()
+            })
+        );
+        let mut alleles = alleles.into_iter();
+
+        let v = alleles.next().unwrap().unwrap();
+        let v = &v[0].1.sequence;
+        assert_eq!(
+            v.sequence.to_string(),
+            "QVQLVQSGAEVKKPGASVKVSCKASGYTFTGYYMHWVRQAPGQGLEWMGRINPNSGGTNYAQKFQGRVTSTRDTSISTAYMELSRLRSDDTAVYYCAR"
+        );
+        assert_eq!(
+            v.regions,
+            [
+                (Region::FR1, 25),
+                (Region::CDR1, 8),
+                (Region::FR2, 17),
+                (Region::CDR2, 8),
+                (Region::FR3, 38),
+                (Region::CDR3, 2)
+            ]
+        );
+        assert_eq!(
+            v.annotations,
+            [
+                (Annotation::Cysteine1, 21),
+                (Annotation::Tryptophan, 35),
+                (Annotation::Cysteine2, 95)
+            ]
+        );
+        assert_eq!(v.provenance.label.as_deref(), Some("IGHV1-2*01"));
+
+        let d = alleles.next().unwrap().unwrap();
+        assert_eq!(d.len(), 3);
+        assert_eq!(d[0].1.sequence.sequence.to_string(), "GTTGT");
+
+        // The same J in every reading frame
+        for _ in 0..3 {
+            let j = alleles.next().unwrap().unwrap();
+            let j = &j[0].1.sequence;
+            assert_eq!(j.sequence.to_string(), "YFDYWGQGTLVTVSS");
+            assert_eq!(j.regions, [(Region::CDR3, 4), (Region::FR4, 11)]);
+            assert_eq!(j.dna_regions, [(Region::CDR3, 12), (Region::FR4, 34)]);
+        }
+
+        let c = alleles.next().unwrap().unwrap();
+        assert_eq!(c[0].1.sequence.sequence.to_string(), "RTVAAPSV");
+        assert_eq!(c[0].1.sequence.regions, [(Region::CL, 8)]);
+
+        assert_eq!(
+            alleles.next().unwrap().unwrap_err().2,
+            GenerateError::MissingRegion("V gene delineation".to_string())
+        );
+
+        // A novel allele is named from its designations but keeps its own label, it is not functional
+        // but AIRR does not record why
+        let novel = alleles.next().unwrap().unwrap();
+        assert_eq!(novel[0].1.name.to_string(), "IGHJ4");
+        assert_eq!(novel[0].1.allele, 2);
+        assert_eq!(
+            novel[0].1.sequence.provenance.label.as_deref(),
+            Some("IGHJ4*02_S1234")
+        );
+        assert_eq!(novel[0].1.sequence.functionality, Functionality::Unknown);
+        assert_eq!(v.functionality, Functionality::Functional);
+    }
+}
@@ -0,0 +1,69 @@
+use std::{cell::RefCell, io::Read, rc::Rc};
+
+use sha2::{Digest, Sha256};
+
+/// A reader that computes the SHA-256 checksum of all data that is read through it
+pub struct HashingReader<R: Read> {
+    inner: R,
+    hasher: Rc<RefCell<Sha256>>,
+}
+
+/// A handle to retrieve the checksum of a [`HashingReader`] once all data is read
+pub struct Checksum(Rc<RefCell<Sha256>>);
+
+impl Checksum {
+    /// The checksum of all data read so far, as lowercase hexadecimal
+    pub fn hex(&self) -> String {
+        format!("{:x}", self.0.borrow().clone().finalize())
+    }
+}
+
+/// Wrap a reader to compute the checksum of all data read
+pub fn hashing_reader<R: Read>(inner: R) -> (HashingReader<R>, Checksum) {
+    let hasher = Rc::new(RefCell::new(Sha256::new()));
+    (
+        HashingReader {
+            inner,
+            hasher: hasher.clone(),
+        },
+        Checksum(hasher),
+    )
+}
+
+impl<R: Read> Read for HashingReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let read = self.inner.read(buf)?;
+        self.hasher.borrow_mut().update(&buf[..read]);
+        Ok(read)
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::missing_panics_doc)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sha256() {
+        let (_, checksum) = hashing_reader(&b""[..]);
+        assert_eq!(
+            checksum.hex(),
+            "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"
+        );
+
+        // Only the data that is read is hashed, also when it is read in parts
+        let (mut reader, checksum) = hashing_reader(&b"abcdef"[..]);
+        let mut buffer = [0; 2];
+        reader.read_exact(&mut buffer).unwrap();
+        reader.read_exact(&mut buffer[..1]).unwrap();
+        assert_eq!(
+            checksum.hex(),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+        reader.read_to_end(&mut Vec::new()).unwrap();
+        assert_eq!(
+            checksum.hex(),
+            "bef57ec7f53a6d40beb640a780a639c83bc29ac8a9816f1fc6c5c6dcd93c4721"
+        );
+    }
+}
@@ -0,0 +1,365 @@
+use std::io::{BufRead, BufReader, Error, ErrorKind, Read, Result};
+
+use flate2::read::MultiGzDecoder;
+
+/// Transparently decompress the data if it is compressed with Unix `compress` (`.Z`) or gzip (`.gz`).
+/// The compression is detected based on the magic bytes at the start of the data, so the extension does not matter.
+pub fn decompress<R: Read + 'static>(reader: R) -> Result<Box<dyn Read>> {
+    let mut reader = BufReader::new(reader);
+    let magic = reader.fill_buf()?.get(..2).map(|m| [m[0], m[1]]);
+    match magic {
+        Some(LZW_MAGIC) => Ok(Box::new(LzwDecoder::new(reader)?)),
+        Some([0x1f, 0x8b]) => Ok(Box::new(MultiGzDecoder::new(reader))),
+        _ => Ok(Box::new(reader)),
+    }
+}
+
+const LZW_MAGIC: [u8; 2] = [0x1f, 0x9d];
+const INIT_BITS: usize = 9;
+const MAX_BITS: usize = 16;
+const CLEAR: usize = 256;
+const FIRST: usize = 257;
+
+/// A streaming decoder for data compressed with the Unix `compress` tool (LZW).
+///
+/// Codes are stored in groups of `n_bits` bytes (8 codes). Whenever the code size changes (or the table
+/// is cleared) the remainder of the current group is skipped, this quirk of `compress` has to be followed
+/// to stay aligned with the encoder.
+pub struct LzwDecoder<R: Read> {
+    inner: R,
+    max_bits: usize,
+    block_mode: bool,
+    /// The current code size in bits
+    n_bits: usize,
+    /// The highest code that fits in the current code size
+    max_code: usize,
+    /// The next free table entry
+    free_entry: usize,
+    /// Set if the table was cleared, the code size is reset when the next group is read
+    clear: bool,
+    prefix: Vec<u16>,
+    suffix: Vec<u8>,
+    /// The current group of codes, with two bytes padding to allow reading codes with a fixed window
+    group: [u8; MAX_BITS + 2],
+    /// The offset in bits in the current group
+    offset: usize,
+    /// The number of bits in the current group that can contain a full code
+    size: usize,
+    old_code: Option<usize>,
+    final_char: u8,
+    stack: Vec<u8>,
+    output: Vec<u8>,
+    output_offset: usize,
+    done: bool,
+}
+
+impl<R: Read> LzwDecoder<R> {
+    /// Start decoding, this reads and checks the header
+    /// # Errors
+    /// If the header could not be read or is not a valid `compress` header
+    pub fn new(mut inner: R) -> Result<Self> {
+        let mut header = [0; 3];
+        inner.read_exact(&mut header)?;
+        if header[..2] != LZW_MAGIC {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                "Not a compressed (.Z) file",
+            ));
+        }
+        let max_bits = usize::from(header[2] & 0x1f);
+        if !(INIT_BITS..=MAX_BITS).contains(&max_bits) {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                format!("Invalid maximal code size in compressed (.Z) file: {max_bits}"),
+            ));
+        }
+        Ok(Self {
+            inner,
+            max_bits,
+            block_mode: header[2] & 0x80 != 0,
+            n_bits: INIT_BITS,
+            max_code: (1 << INIT_BITS) - 1,
+            free_entry: if header[2] & 0x80 != 0 { FIRST } else { 256 },
+            clear: false,
+            prefix: vec![0; 1 << max_bits],
+            suffix: vec![0; 1 << max_bits],
+            group: [0; MAX_BITS + 2],
+            offset: 0,
+            size: 0,
+            old_code: None,
+            final_char: 0,
+            stack: Vec::new(),
+            output: Vec::new(),
+            output_offset: 0,
+            done: false,
+        })
+    }
+
+    /// Read the next code, returns None at the end of the data
+    fn next_code(&mut self) -> Result<Option<usize>> {
+        if self.clear || self.offset >= self.size || self.free_entry > self.max_code {
+            // Start a new group, any codes left in the current group are skipped
+            if self.free_entry > self.max_code {
+                self.n_bits += 1;
+                self.max_code = if self.n_bits == self.max_bits {
+                    1 << self.max_bits
+                } else {
+                    (1 << self.n_bits) - 1
+                };
+            }
+            if self.clear {
+                self.n_bits = INIT_BITS;
+                self.max_code = (1 << INIT_BITS) - 1;
+                self.clear = false;
+            }
+            let mut read = 0;
+            while read < self.n_bits {
+                match self.inner.read(&mut self.group[read..self.n_bits]) {
+                    Ok(0) => break,
+                    Ok(n) => read += n,
+                    Err(e) if e.kind() == ErrorKind::Interrupted => (),
+                    Err(e) => return Err(e),
+                }
+            }
+            self.offset = 0;
+            // Only count the bits that can hold a full code
+            self.size = (read * 8).saturating_sub(self.n_bits - 1);
+            if self.size == 0 {
+                return Ok(None);
+            }
+        }
+        let byte = self.offset / 8;
+        let window = u32::from(self.group[byte])
+            | u32::from(self.group[byte + 1]) << 8
+            | u32::from(self.group[byte + 2]) << 16;
+        let code = (window >> (self.offset % 8)) as usize & ((1 << self.n_bits) - 1);
+        self.offset += self.n_bits;
+        Ok(Some(code))
+    }
+
+    /// Decode the next code into the output buffer, sets `done` at the end of the data
+    fn decode_next(&mut self) -> Result<()> {
+        let Some(mut code) = self.next_code()? else {
+            self.done = true;
+            return Ok(());
+        };
+        let Some(old_code) = self.old_code else {
+            // The first code is always a literal
+            self.final_char = u8::try_from(code).map_err(|_| {
+                Error::new(
+                    ErrorKind::InvalidData,
+                    "Invalid first code in compressed (.Z) file",
+                )
+            })?;
+            self.output.push(self.final_char);
+            self.old_code = Some(code);
+            return Ok(());
+        };
+        if code == CLEAR && self.block_mode {
+            self.clear = true;
+            self.free_entry = FIRST - 1;
+            let Some(next) = self.next_code()? else {
+                self.done = true;
+                return Ok(());
+            };
+            code = next;
+        }
+        let in_code = code;
+        if code >= self.free_entry {
+            // The code that is defined by this step (KwKwK case)
+            if code > self.free_entry {
+                return Err(Error::new(
+                    ErrorKind::InvalidData,
+                    "Invalid code in compressed (.Z) file",
+                ));
+            }
+            self.stack.push(self.final_char);
+            code = old_code;
+        }
+        while code >= 256 {
+            if self.stack.len() >= self.suffix.len() {
+                return Err(Error::new(
+                    ErrorKind::InvalidData,
+                    "Cyclic code in compressed (.Z) file",
+                ));
+            }
+            self.stack.push(self.suffix[code]);
+            code = usize::from(self.prefix[code]);
+        }
+        self.final_char = code as u8;
+        self.stack.push(self.final_char);
+        self.output.extend(self.stack.drain(..).rev());
+
+        if self.free_entry < 1 << self.max_bits {
+            self.prefix[self.free_entry] = old_code as u16;
+            self.suffix[self.free_entry] = self.final_char;
+            self.free_entry += 1;
+        }
+        self.old_code = Some(in_code);
+        Ok(())
+    }
+}
+
+impl<R: Read> Read for LzwDecoder<R> {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
+        if self.output_offset == self.output.len() {
+            self.output.clear();
+            self.output_offset = 0;
+            while self.output.len() < buf.len() && !self.done {
+                self.decode_next()?;
+            }
+        }
+        let length = buf.len().min(self.output.len() - self.output_offset);
+        buf[..length]
+            .copy_from_slice(&self.output[self.output_offset..self.output_offset + length]);
+        self.output_offset += length;
+        Ok(length)
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::missing_panics_doc)]
+mod tests {
+    use std::{collections::HashMap, io::Write};
+
+    use super::*;
+
+    /// Compress the data in the same way as the Unix `compress` tool, the table is cleared whenever it is full
+    fn compress(data: &[u8], max_bits: usize) -> Vec<u8> {
+        struct Writer {
+            output: Vec<u8>,
+            group: Vec<u8>,
+            offset: usize,
+            n_bits: usize,
+        }
+        impl Writer {
+            fn flush(&mut self, bytes: usize) {
+                self.output.extend(&self.group[..bytes]);
+                self.group = vec![0; self.n_bits];
+                self.offset = 0;
+            }
+            fn write(&mut self, code: usize) {
+                for bit in 0..self.n_bits {
+                    if code >> bit & 1 == 1 {
+                        self.group[(self.offset + bit) / 8] |= 1 << ((self.offset + bit) % 8);
+                    }
+                }
+                self.offset += self.n_bits;
+                if self.offset == self.n_bits * 8 {
+                    self.flush(self.n_bits);
+                }
+            }
+            /// Change the code size, the rest of the current group is written as padding
+            fn resize(&mut self, n_bits: usize) {
+                if self.offset > 0 {
+                    self.flush(self.n_bits);
+                }
+                self.n_bits = n_bits;
+                self.group = vec![0; n_bits];
+            }
+        }
+
+        let mut writer = Writer {
+            output: vec![LZW_MAGIC[0], LZW_MAGIC[1], 0x80 | max_bits as u8],
+            group: vec![0; INIT_BITS],
+            offset: 0,
+            n_bits: INIT_BITS,
+        };
+        let mut table = HashMap::new();
+        let mut free_entry = FIRST;
+        let mut max_code = (1 << INIT_BITS) - 1;
+        let mut output = |writer: &mut Writer, code: usize, free_entry: usize, clear: bool| {
+            writer.write(code);
+            if clear {
+                writer.resize(INIT_BITS);
+                max_code = (1 << INIT_BITS) - 1;
+            } else if free_entry > max_code {
+                writer.resize(writer.n_bits + 1);
+                max_code = if writer.n_bits == max_bits {
+                    1 << max_bits
+                } else {
+                    (1 << writer.n_bits) - 1
+                };
+            }
+        };
+        let mut current = usize::from(data[0]);
+        for &byte in &data[1..] {
+            if let Some(code) = table.get(&(current, byte)) {
+                current = *code;
+                continue;
+            }
+            output(&mut writer, current, free_entry, false);
+            if free_entry < 1 << max_bits {
+                table.insert((current, byte), free_entry);
+                free_entry += 1;
+            } else {
+                output(&mut writer, CLEAR, free_entry, true);
+                table.clear();
+                free_entry = FIRST;
+            }
+            current = usize::from(byte);
+        }
+        output(&mut writer, current, free_entry, false);
+        let bytes = writer.offset.div_ceil(8);
+        writer.flush(bytes);
+        writer.output
+    }
+
+    /// Pseudo random DNA, to fill the code table
+    fn dna(length: usize) -> Vec<u8> {
+        let mut state = 12_345_u32;
+        (0..length)
+            .map(|_| {
+                state = state.wrapping_mul(1_103_515_245).wrapping_add(12_345);
+                b"acgt"[(state >> 16) as usize % 4]
+            })
+            .collect()
+    }
+
+    #[test]
+    fn lzw_round_trip() {
+        for (data, max_bits) in [
+            (b"abababababababab".to_vec(), 16),
+            (dna(1_000), 16),
+            (dna(100_000), 12),
+            (dna(100_000), 16),
+        ] {
+            let mut decompressed = Vec::new();
+            decompress(std::io::Cursor::new(compress(&data, max_bits)))
+                .unwrap()
+                .read_to_end(&mut decompressed)
+                .unwrap();
+            assert_eq!(decompressed, data);
+        }
+    }
+
+    /// A FASTA file compressed in block mode with up to 16 bit codes, it is long enough to switch from 9 to
+    /// 10 bit codes. `gzip -d` decompresses it to the same FASTA file.
+    #[test]
+    fn compress_fixture() {
+        let compressed = include_bytes!("../test_data/germlines.fasta.Z");
+        assert_eq!(compressed[..3], [LZW_MAGIC[0], LZW_MAGIC[1], 0x80 | 16]);
+        let mut decompressed = String::new();
+        decompress(&compressed[..])
+            .unwrap()
+            .read_to_string(&mut decompressed)
+            .unwrap();
+        assert_eq!(decompressed, include_str!("../test_data/germlines.fasta"));
+    }
+
+    #[test]
+    fn gzip_and_plain() {
+        let data = dna(10_000);
+        let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder.write_all(&data).unwrap();
+        for input in [encoder.finish().unwrap(), data.clone()] {
+            let mut decompressed = Vec::new();
+            decompress(std::io::Cursor::new(input))
+                .unwrap()
+                .read_to_end(&mut decompressed)
+                .unwrap();
+            assert_eq!(decompressed, data);
+        }
+        assert!(LzwDecoder::new(&[0x1f, 0x9d, 0x80 | 20][..]).is_err());
+    }
+}
@@ -0,0 +1,313 @@
+use std::{
+    collections::BTreeMap,
+    fmt::Display,
+    fs::File,
+    io::{BufWriter, Write},
+    path::Path,
+};
+
+use itertools::Itertools;
+
+use crate::shared::*;
+
+/// The identity of a single allele sequence: species, gene, allele number, reading frame, and isoform
+type AlleleKey = (Species, Gene, usize, Option<usize>, Option<Isoform>);
+
+/// A single change for an allele between two sets of germlines
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Change {
+    Added,
+    Removed,
+    /// The same sequence is now known under a different name
+    Renamed(String),
+    Sequence(String, String),
+    Regions(String, String),
+    Annotations(String, String),
+    Functionality(Functionality, Functionality),
+}
+
+impl Change {
+    const fn kind(&self) -> &'static str {
+        match self {
+            Self::Added => "added",
+            Self::Removed => "removed",
+            Self::Renamed(_) => "renamed",
+            Self::Sequence(..) => "sequence",
+            Self::Regions(..) => "regions",
+            Self::Annotations(..) => "annotations",
+            Self::Functionality(..) => "functionality",
+        }
+    }
+}
+
+impl Display for Change {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Added | Self::Removed => Ok(()),
+            Self::Renamed(from) => write!(f, "from {from}"),
+            Self::Sequence(old, new) | Self::Regions(old, new) | Self::Annotations(old, new) => {
+                write!(f, "{old} -> {new}")
+            }
+            Self::Functionality(old, new) => write!(f, "{old} -> {new}"),
+        }
+    }
+}
+
+/// Compare the generated germlines (all `.bin` files) in two directories and write a changelog as TSV.
+/// Returns the number of changes of every kind, sorted on kind. Both directories have to be generated
+/// with the current layout of the germlines, older `.bin` files cannot be read and give an error.
+pub fn diff(old: &Path, new: &Path, output: &Path) -> Result<Vec<(&'static str, usize)>, String> {
+    let old_germlines = load(old)?;
+    let new_germlines = load(new)?;
+    let old_alleles = alleles(&old_germlines);
+    let new_alleles = alleles(&new_germlines);
+
+    let mut changes: Vec<(&AlleleKey, Change)> = Vec::new();
+    for (key, old_seq) in &old_alleles {
+        if let Some(new_seq) = new_alleles.get(key) {
+            if old_seq.sequence != new_seq.sequence {
+                changes.push((
+                    key,
+                    Change::Sequence(old_seq.sequence.to_string(), new_seq.sequence.to_string()),
+                ));
+            }
+            if old_seq.regions != new_seq.regions {
+                changes.push((
+                    key,
+                    Change::Regions(
+                        show_annotations(&old_seq.regions),
+                        show_annotations(&new_seq.regions),
+                    ),
+                ));
+            }
+            if old_seq.annotations != new_seq.annotations {
+                changes.push((
+                    key,
+                    Change::Annotations(
+                        show_annotations(&old_seq.annotations),
+                        show_annotations(&new_seq.annotations),
+                    ),
+                ));
+            }
+            if old_seq.functionality != new_seq.functionality {
+                changes.push((
+                    key,
+                    Change::Functionality(old_seq.functionality, new_seq.functionality),
+                ));
+            }
+        }
+    }
+
+    // Removed alleles with the exact same sequence as an added allele are renames
+    let mut added = new_alleles
+        .iter()
+        .filter(|(key, _)| !old_alleles.contains_key(*key))
+        .collect_vec();
+    for (key, old_seq) in old_alleles
+        .iter()
+        .filter(|(key, _)| !new_alleles.contains_key(*key))
+    {
+        if let Some(index) = added.iter().position(|(new_key, new_seq)| {
+            new_key.0 == key.0
+                && new_key.1.chain == key.1.chain
+                && std::mem::discriminant(&new_key.1.gene) == std::mem::discriminant(&key.1.gene)
+                && new_key.3 == key.3
+                && new_key.4 == key.4
+                && new_seq.sequence == old_seq.sequence
+        }) {
+            let (new_key, _) = added.remove(index);
+            changes.push((new_key, Change::Renamed(allele_name(key))));
+        } else {
+            changes.push((key, Change::Removed));
+        }
+    }
+    changes.extend(added.into_iter().map(|(key, _)| (key, Change::Added)));
+    changes.sort_by(|a, b| a.0.cmp(b.0).then(a.1.kind().cmp(b.1.kind())));
+
+    let mut file = BufWriter::new(
+        File::create(output)
+            .map_err(|e| format!("Could not create '{}': {e}", output.display()))?,
+    );
+    let write_error = |e: std::io::Error| format!("Could not write changelog: {e}");
+    writeln!(
+        file,
+        "species\tchain\tgene\tallele\tvariant\tchange\tdetails"
+    )
+    .map_err(write_error)?;
+    for (key, change) in &changes {
+        writeln!(
+            file,
+            "{}\t{}\t{}\t{}\t{}\t{}\t{change}",
+            key.0.scientific_name(),
+            key.1.chain,
+            key.1.gene,
+            allele_name(key),
+            variant(key),
+            change.kind(),
+        )
+        .map_err(write_error)?;
+    }
+
+    Ok(changes
+        .iter()
+        .map(|(_, c)| c.kind())
+        .counts()
+        .into_iter()
+        .sorted()
+        .collect())
+}
+
+/// Load all generated germlines (`.bin` files) from the given directory
+fn load(directory: &Path) -> Result<Vec<Germlines>, String> {
+    let mut germlines = Vec::new();
+    for entry in std::fs::read_dir(directory)
+        .map_err(|e| format!("Could not read directory '{}': {e}", directory.display()))?
+    {
+        let path = entry.map_err(|e| e.to_string())?.path();
+        if path.extension().is_some_and(|e| e == "bin") {
+            let bytes = std::fs::read(&path)
+                .map_err(|e| format!("Could not read '{}': {e}", path.display()))?;
+            germlines.push(bincode::deserialize::<Germlines>(&bytes).map_err(|e| {
+                format!(
+                    "Invalid germlines in '{}', it might be generated with an older layout: {e}",
+                    path.display()
+                )
+            })?);
+        }
+    }
+    Ok(germlines)
+}
+
+/// Get all allele sequences from the given germlines
+fn alleles(germlines: &[Germlines]) -> BTreeMap<AlleleKey, &AnnotatedSequence> {
+    germlines
+        .iter()
+        .filter_map(|germlines| germlines.species.map(|species| (species, germlines)))
+        .flat_map(|(species, germlines)| {
+            germlines.into_iter().flat_map(move |(_, chain)| {
+                chain.into_iter().flat_map(move |(_, genes)| {
+                    genes.iter().flat_map(move |germline| {
+                        germline.alleles.iter().map(move |(allele, seq)| {
+                            (
+                                (
+                                    species,
+                                    germline.name.clone(),
+                                    *allele,
+                                    seq.reading_frame,
+                                    seq.isoform,
+                                ),
+                                seq,
+                            )
+                        })
+                    })
+                })
+            })
+        })
+        .collect()
+}
+
+fn allele_name(key: &AlleleKey) -> String {
+    format!("{}*{:02}", key.1, key.2)
+}
+
+fn variant(key: &AlleleKey) -> String {
+    match (key.3, key.4) {
+        (Some(frame), _) => format!("frame {frame}"),
+        (None, Some(isoform)) => isoform.to_string(),
+        (None, None) => "-".to_string(),
+    }
+}
+
+fn show_annotations<T: Display>(annotations: &[(T, usize)]) -> String {
+    annotations
+        .iter()
+        .map(|(a, i)| format!("[{a},{i}]"))
+        .collect()
+}
+
+#[cfg(test)]
+#[allow(clippy::missing_panics_doc)]
+mod tests {
+    use rustyms::AminoAcid;
+
+    use super::*;
+
+    /// Write the given V alleles as a germline set in the directory
+    fn write(directory: &Path, alleles: &[(&str, &str, Functionality)]) {
+        let mut germlines = Germlines::new(Species::HomoSapiens);
+        for (name, sequence, functionality) in alleles {
+            let (name, allele) = Gene::from_imgt_name_with_allele(name).unwrap();
+            let sequence = sequence
+                .bytes()
+                .map(|b| AminoAcid::try_from(b).unwrap())
+                .collect_vec();
+            germlines.insert(Germline {
+                name,
+                alleles: vec![(
+                    allele,
+                    AnnotatedSequence {
+                        functionality: *functionality,
+                        ..AnnotatedSequence::new(
+                            sequence.into(),
+                            vec![(Region::FR1, 5)],
+                            Vec::new(),
+                        )
+                    },
+                )],
+            });
+        }
+        std::fs::create_dir_all(directory).unwrap();
+        std::fs::write(
+            directory.join("HomoSapiens.bin"),
+            bincode::serialize(&germlines).unwrap(),
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn changelog() {
+        let directory = std::env::temp_dir().join(format!("imgt-diff-{}", std::process::id()));
+        let (old, new) = (directory.join("old"), directory.join("new"));
+        write(
+            &old,
+            &[
+                ("IGHV1-2*01", "QVQLV", Functionality::Functional),
+                ("IGHV1-3*01", "EVQLL", Functionality::Functional),
+                ("IGHV1-8*01", "AAAAA", Functionality::Functional),
+            ],
+        );
+        write(
+            &new,
+            &[
+                ("IGHV1-2*01", "QVQLL", Functionality::OpenReadingFrame),
+                ("IGHV1-4*01", "EVQLL", Functionality::Functional),
+                ("IGHV1-9*01", "CCCCC", Functionality::Functional),
+            ],
+        );
+        let output = directory.join("changes.tsv");
+        let counts = diff(&old, &new, &output).unwrap();
+        let changes = std::fs::read_to_string(&output).unwrap();
+        std::fs::remove_dir_all(&directory).unwrap();
+        assert_eq!(
+            changes.lines().collect_vec(),
+            [
+                "species\tchain\tgene\tallele\tvariant\tchange\tdetails",
+                "Homo sapiens\tH\tV\tIGHV1-2*01\t-\tfunctionality\tF -> ORF",
+                "Homo sapiens\tH\tV\tIGHV1-2*01\t-\tsequence\tQVQLV -> QVQLL",
+                "Homo sapiens\tH\tV\tIGHV1-4*01\t-\trenamed\tfrom IGHV1-3*01",
+                "Homo sapiens\tH\tV\tIGHV1-8*01\t-\tremoved\t",
+                "Homo sapiens\tH\tV\tIGHV1-9*01\t-\tadded\t",
+            ]
+        );
+        assert_eq!(
+            counts,
+            [
+                ("added", 1),
+                ("functionality", 1),
+                ("removed", 1),
+                ("renamed", 1),
+                ("sequence", 1)
+            ]
+        );
+    }
+}
@@ -0,0 +1,94 @@
+use std::fmt::Display;
+
+/// All reasons why an entry or gene from the IMGT data could not be used
+#[derive(Clone, Debug, Hash, Eq, PartialEq)]
+pub enum GenerateError {
+    /// The entry has no species
+    MissingSpecies,
+    /// The species name of the entry is not recognised
+    UnknownSpecies(String),
+    /// The location could not be parsed
+    InvalidLocation(String),
+    /// The location is between two nucleotides (`^`), which is not supported
+    UnsupportedLocation(String),
+    /// The codon start is not a valid number
+    InvalidCodonStart(String),
+    /// The location (possibly shifted by the codon start) falls outside of the sequence of the entry
+    OutOfRange(&'static str),
+    /// The DNA contains a codon that could not be translated
+    Translation(String),
+    /// The translation reported by IMGT does not match the translation of the DNA, as (reported, found)
+    TranslationMismatch(String, String),
+    /// A region needed to build the gene is not present
+    MissingRegion(String),
+    /// The gene has no functionality (F, ORF, or P) annotated
+    MissingFunctionality,
+    /// A partial gene misses regions in the middle or has no known truncated end
+    InvalidPartial(String),
+    /// The IMGT name of the gene could not be parsed
+    NameParse(String),
+    /// A constant gene without any constant domains
+    EmptyConstant,
+    /// A conserved residue that does not fall in any of the regions of the gene
+    MissingAnnotation(String),
+    /// The header of a FASTA record could not be parsed
+    InvalidHeader(String),
+    /// The label of a FASTA record is not supported
+    UnsupportedLabel(String),
+    /// The final sequence violates one of the consistency checks
+    Invalid(String),
+}
+
+impl GenerateError {
+    /// A short name for the category of this error, used for statistics
+    pub const fn category(&self) -> &'static str {
+        match self {
+            Self::MissingSpecies => "missing species",
+            Self::UnknownSpecies(_) => "unknown species",
+            Self::InvalidLocation(_) => "invalid location",
+            Self::UnsupportedLocation(_) => "unsupported location",
+            Self::InvalidCodonStart(_) => "invalid codon start",
+            Self::OutOfRange(_) => "location out of range",
+            Self::Translation(_) => "translation failure",
+            Self::TranslationMismatch(..) => "translation mismatch",
+            Self::MissingRegion(_) => "missing region",
+            Self::MissingFunctionality => "missing functionality",
+            Self::InvalidPartial(_) => "invalid partial gene",
+            Self::NameParse(_) => "name parse failure",
+            Self::EmptyConstant => "empty constant gene",
+            Self::MissingAnnotation(_) => "missing annotation",
+            Self::InvalidHeader(_) => "invalid header",
+            Self::UnsupportedLabel(_) => "unsupported label",
+            Self::Invalid(_) => "failed validation",
+        }
+    }
+}
+
+impl Display for GenerateError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::MissingSpecies => write!(f, "No species found"),
+            Self::UnknownSpecies(name) => write!(f, "Not a species name: `{name}`"),
+            Self::InvalidLocation(msg) => write!(f, "Invalid location: {msg}"),
+            Self::UnsupportedLocation(location) => {
+                write!(f, "Location is a ^ region: `{location}`")
+            }
+            Self::InvalidCodonStart(start) => write!(f, "Not a valid codon_start: '{start}'"),
+            Self::OutOfRange(msg) => write!(f, "{msg}"),
+            Self::Translation(codon) => write!(f, "Not a codon {codon}"),
+            Self::TranslationMismatch(reported, found) => write!(
+                f,
+                "IMGT reported translation {reported} but the DNA translates to {found}"
+            ),
+            Self::MissingRegion(key) => write!(f, "Could not find {key}"),
+            Self::MissingFunctionality => write!(f, "No functionality annotated"),
+            Self::InvalidPartial(msg) => write!(f, "{msg}"),
+            Self::NameParse(msg) => write!(f, "Invalid gene name: {msg}"),
+            Self::EmptyConstant => write!(f, "Empty C sequence"),
+            Self::MissingAnnotation(msg) => write!(f, "Cannot find location of {msg}"),
+            Self::InvalidHeader(header) => write!(f, "Invalid FASTA header: `{header}`"),
+            Self::UnsupportedLabel(label) => write!(f, "Unsupported label: {label}"),
+            Self::Invalid(msg) => write!(f, "Invalid sequence: {msg}"),
+        }
+    }
+}
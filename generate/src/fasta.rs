@@ -0,0 +1,608 @@
+use std::{
+    collections::HashMap,
+    io::{BufRead, BufReader, Read},
+};
+
+use itertools::Itertools;
+use rustyms::AminoAcid;
+
+use crate::{
+    error::GenerateError, j_motif_annotations, shared::*, translate, translate_d_frame, SingleSeq,
+};
+
+/// The regions of V genes in the IMGT unique numbering, as 1-based inclusive nucleotide positions in the IMGT gapped sequence
+const V_REGIONS: [(Region, usize, usize); 6] = [
+    (Region::FR1, 1, 78),
+    (Region::CDR1, 79, 114),
+    (Region::FR2, 115, 165),
+    (Region::CDR2, 166, 195),
+    (Region::FR3, 196, 312),
+    (Region::CDR3, 313, usize::MAX),
+];
+
+/// The conserved residues of V genes in the IMGT unique numbering, as 1-based codon positions in the IMGT gapped sequence
+const V_CONSERVED: [(Annotation, usize, AminoAcid); 3] = [
+    (Annotation::Cysteine1, 23, AminoAcid::C),
+    (Annotation::Tryptophan, 41, AminoAcid::W),
+    (Annotation::Cysteine2, 104, AminoAcid::C),
+];
+
+/// The amino acid sequence with its regions and annotations, and the DNA with its regions
+pub type Translated = (
+    Vec<AminoAcid>,
+    Vec<(Region, usize)>,
+    Vec<(Annotation, usize)>,
+    String,
+    Vec<(Region, usize)>,
+);
+
+/// The error for a record, with the species (if known) and the accession and allele name of the record
+type RecordError = (Option<Species>, String, GenerateError);
+
+/// The exons of a heavy chain constant gene, with the codon start (0-based) and DNA of each exon
+type Exons = Vec<(Region, usize, String)>;
+
+/// Parse an IMGT/GENE-DB reference directory FASTA file (eg `IGHV.fasta`). The headers contain the
+/// allele name, species, functionality, label, and codon start. Only V-REGION, D-REGION, J-REGION, and
+/// C-REGION records are used, except for heavy chain constant genes which are built from the records of
+/// their separate exons (CH1, H, CH2, CH3, CHS, M, etc). V genes have to be IMGT gapped, the gaps are used
+/// to determine the regions and conserved residues based on the IMGT unique numbering.
+pub fn parse_fasta<T: Read>(
+    reader: BufReader<T>,
+    keep_partial: bool,
+) -> impl Iterator<Item = Result<Vec<(Species, SingleSeq)>, RecordError>> {
+    let mut lines = reader.lines().map_while(Result::ok).peekable();
+    let mut heavy_constants: Vec<(GeneInfo, Exons)> = Vec::new();
+    let records = std::iter::from_fn(move || {
+        let header = loop {
+            if let Some(header) = lines.next()?.strip_prefix('>') {
+                break header.to_string();
+            }
+        };
+        let mut sequence = String::new();
+        while let Some(line) = lines.next_if(|line| !line.starts_with('>')) {
+            sequence.push_str(line.trim());
+        }
+        Some((header, sequence.to_ascii_lowercase()))
+    })
+    .filter_map(|(header, sequence)| {
+        parse_record(&header, &sequence, keep_partial, &mut heavy_constants).transpose()
+    })
+    .collect_vec();
+    records
+        .into_iter()
+        .chain(heavy_constants.into_iter().map(|(info, exons)| {
+            heavy_constant(&info, exons).map_err(|err| (Some(info.species), info.acc.clone(), err))
+        }))
+}
+
+/// Parse a single record, returns None if the record is not a gene or is partial while partial genes are not
+/// kept. The exons of heavy chain constant genes are stored in `heavy_constants` to be combined later.
+fn parse_record(
+    header: &str,
+    sequence: &str,
+    keep_partial: bool,
+    heavy_constants: &mut Vec<(GeneInfo, Exons)>,
+) -> Result<Option<Vec<(Species, SingleSeq)>>, RecordError> {
+    let fields = header.split('|').map(str::trim).collect_vec();
+    let id = fields.iter().take(2).join("|");
+    if fields.len() < 8 {
+        return Err((None, id, GenerateError::InvalidHeader(header.to_string())));
+    }
+    let species = fields[2]
+        .split('_')
+        .next()
+        .and_then(|name| name.parse::<Species>().ok())
+        .ok_or_else(|| (None, id.clone(), GenerateError::MissingSpecies))?;
+    let error = |err| (Some(species), id.clone(), err);
+    let exon = constant_exon(fields[4]);
+    if exon.is_none() && !["V-REGION", "D-REGION", "J-REGION", "C-REGION"].contains(&fields[4]) {
+        return Ok(None);
+    }
+    let functionality = match fields[3]
+        .trim_matches(['(', ')', '[', ']'])
+        .to_ascii_lowercase()
+        .replace("in frame", "in-frame")
+        .as_str()
+    {
+        "f" => Functionality::Functional,
+        "orf" => Functionality::OpenReadingFrame,
+        "p" => Functionality::Pseudogene,
+        "in-frame p" | "p in-frame" | "in-frame pseudogene" => Functionality::InFramePseudogene,
+        _ => return Err(error(GenerateError::InvalidHeader(header.to_string()))),
+    };
+    let partial = fields.get(13).copied().unwrap_or_default();
+    let truncation = Truncation {
+        five_prime: partial.contains("5'"),
+        three_prime: partial.contains("3'"),
+    };
+    if truncation.is_partial() && !keep_partial {
+        return Ok(None);
+    }
+    let shift = match fields[7] {
+        "NR" | "" => 0,
+        start => start
+            .parse::<usize>()
+            .ok()
+            .and_then(|start| start.checked_sub(1))
+            .ok_or_else(|| error(GenerateError::InvalidCodonStart(start.to_string())))?,
+    };
+    let (name, allele) = Gene::from_imgt_name_with_allele(fields[1])
+        .map_err(|e| error(GenerateError::NameParse(e)))?;
+    let ungapped = sequence.replace('.', "");
+    let info = GeneInfo {
+        species,
+        name,
+        allele,
+        acc: fields[0].to_string(),
+        label: None,
+        functionality,
+        truncation,
+    };
+
+    // Heavy chain constant genes are built from their exon records once all records are read, the
+    // C-REGION record is only kept to report the gene if there are no exon records
+    if info.name.chain == ChainType::Heavy && matches!(info.name.gene, GeneType::C(_)) {
+        let index = heavy_constants
+            .iter()
+            .position(|(other, _)| {
+                other.species == info.species
+                    && other.name == info.name
+                    && other.allele == info.allele
+            })
+            .unwrap_or_else(|| {
+                heavy_constants.push((info, Vec::new()));
+                heavy_constants.len() - 1
+            });
+        if let Some(exon) = exon {
+            heavy_constants[index].1.push((exon, shift, ungapped));
+        }
+        return Ok(None);
+    } else if exon.is_some() {
+        return Ok(None);
+    }
+    match fields[4] {
+        "V-REGION" => Ok(Some(vec![info.single_seq(
+            v_region(sequence).map_err(error)?,
+            None,
+            None,
+        )])),
+        "D-REGION" => Ok(Some(
+            d_regions(&ungapped)
+                .map_err(error)?
+                .into_iter()
+                .map(|(frame, translated)| info.single_seq(translated, Some(frame), None))
+                .collect(),
+        )),
+        "J-REGION" => Ok(Some(vec![info.single_seq(
+            j_region(ungapped.get(shift..).unwrap_or_default(), None).map_err(error)?,
+            None,
+            None,
+        )])),
+        _ => Ok(Some(vec![info.single_seq(
+            c_region(ungapped.get(shift..).unwrap_or_default(), info.name.chain).map_err(error)?,
+            None,
+            Some(Isoform::Secreted),
+        )])),
+    }
+}
+
+/// The information on a gene from a germline source, shared between all sequences built for the gene
+pub struct GeneInfo {
+    pub species: Species,
+    pub name: Gene,
+    pub allele: usize,
+    /// The identifier of the sequence in the source
+    pub acc: String,
+    /// The allele label in the source, if it does not use IMGT names
+    pub label: Option<String>,
+    pub functionality: Functionality,
+    pub truncation: Truncation,
+}
+
+impl GeneInfo {
+    /// Build a sequence for this gene
+    pub fn single_seq(
+        &self,
+        (aa, regions, annotations, dna, dna_regions): Translated,
+        reading_frame: Option<usize>,
+        isoform: Option<Isoform>,
+    ) -> (Species, SingleSeq) {
+        let mut sequence = AnnotatedSequence {
+            reading_frame,
+            isoform,
+            functionality: self.functionality,
+            dna,
+            dna_regions,
+            truncation: self.truncation,
+            ..AnnotatedSequence::new(aa.into(), regions, annotations)
+        };
+        sequence.provenance.label.clone_from(&self.label);
+        (
+            self.species,
+            SingleSeq {
+                name: self.name.clone(),
+                allele: self.allele,
+                acc: self.acc.clone(),
+                sequence,
+            },
+        )
+    }
+}
+
+/// Translate a D-REGION in all three reading frames, frames with a stop codon are skipped
+pub fn d_regions(sequence: &str) -> Result<Vec<(usize, Translated)>, GenerateError> {
+    (0..3)
+        .filter_map(|frame| {
+            translate_d_frame(sequence, frame)
+                .transpose()
+                .map(|t| (frame, t))
+        })
+        .map(|(frame, translated)| {
+            let (dna, aa) = translated?;
+            Ok((
+                frame,
+                (
+                    aa.clone(),
+                    vec![(Region::CDR3, aa.len())],
+                    Vec::new(),
+                    dna.to_string(),
+                    vec![(Region::CDR3, dna.len())],
+                ),
+            ))
+        })
+        .collect()
+}
+
+/// Get the region for the label of a record of a separate exon of a heavy chain constant gene
+fn constant_exon(label: &str) -> Option<Region> {
+    match label {
+        "H-CH2" => Some(Region::H_CH2),
+        "CH1" | "H" | "H1" | "H2" | "H3" | "H4" | "CHS" | "M" | "M1" | "M2" => label.parse().ok(),
+        _ if label.starts_with("CH") => label.parse().ok(),
+        _ => None,
+    }
+}
+
+/// The position of an exon in a heavy chain constant gene, used to sort the exon records
+const fn exon_order(region: Region) -> (usize, usize) {
+    match region {
+        Region::CH1 => (1, 0),
+        Region::Hinge(exon, _) => (1, exon),
+        Region::H_CH2 | Region::CH2 | Region::CH2_CHS => (2, 0),
+        Region::CH3 | Region::CH3_CHS => (3, 0),
+        Region::CH4 | Region::CH4_CHS => (4, 0),
+        Region::CH5 | Region::CH5_CHS => (5, 0),
+        Region::CH6 | Region::CH6_CHS => (6, 0),
+        Region::CH7 | Region::CH7_CHS => (7, 0),
+        Region::CH8 | Region::CH8_CHS => (8, 0),
+        Region::CH9 | Region::CH9_CHS => (9, 0),
+        Region::CHS => (10, 0),
+        Region::M => (11, 0),
+        Region::M1 => (11, 1),
+        Region::M2 => (11, 2),
+        _ => (0, 0),
+    }
+}
+
+/// Get the domain without the secretory tail for a domain that includes the secretory tail
+const fn without_secretory_tail(region: Region) -> Option<Region> {
+    match region {
+        Region::CH2_CHS => Some(Region::CH2),
+        Region::CH3_CHS => Some(Region::CH3),
+        Region::CH4_CHS => Some(Region::CH4),
+        Region::CH5_CHS => Some(Region::CH5),
+        Region::CH6_CHS => Some(Region::CH6),
+        Region::CH7_CHS => Some(Region::CH7),
+        Region::CH8_CHS => Some(Region::CH8),
+        Region::CH9_CHS => Some(Region::CH9),
+        _ => None,
+    }
+}
+
+/// Build a heavy chain constant gene from the records of its exons. This gives the secreted isoform, and the
+/// membrane bound isoform if the membrane exons are present. The hinge exons are kept whole.
+/// # Errors
+/// If there are no exon records or constant domains, or if the spliced DNA could not be translated.
+fn heavy_constant(
+    info: &GeneInfo,
+    mut exons: Exons,
+) -> Result<Vec<(Species, SingleSeq)>, GenerateError> {
+    if exons.is_empty() {
+        return Err(GenerateError::UnsupportedLabel(
+            "C-REGION without the constant domains".to_string(),
+        ));
+    }
+    exons.sort_by_key(|exon| exon_order(exon.0));
+    let has = |region: Region| exons.iter().any(|exon| exon.0 == region);
+    // The domains and secretory tail can be given separately, as a domain including the secretory tail, or both
+    let separate_tail = has(Region::CHS)
+        && exons
+            .iter()
+            .filter_map(|exon| without_secretory_tail(exon.0))
+            .all(has);
+    let secreted = exons
+        .iter()
+        .filter(|(region, ..)| match region {
+            Region::M | Region::M1 | Region::M2 => false,
+            Region::CHS => separate_tail,
+            region if without_secretory_tail(*region).is_some() => !separate_tail,
+            region => {
+                separate_tail
+                    || !exons
+                        .iter()
+                        .any(|exon| without_secretory_tail(exon.0) == Some(*region))
+            }
+        })
+        .collect_vec();
+    if secreted.is_empty() {
+        return Err(GenerateError::EmptyConstant);
+    }
+    let mut output = vec![info.single_seq(splice(&secreted)?, None, Some(Isoform::Secreted))];
+
+    // The membrane bound form splices the last domain without its secretory tail to the membrane exons
+    if exons
+        .iter()
+        .any(|exon| matches!(exon.0, Region::M | Region::M1 | Region::M2))
+    {
+        let membrane = exons
+            .iter()
+            .filter(|exon| exon.0 != Region::CHS && without_secretory_tail(exon.0).is_none())
+            .collect_vec();
+        let complete = exons
+            .iter()
+            .filter_map(|exon| without_secretory_tail(exon.0))
+            .all(has);
+        if complete {
+            output.push(info.single_seq(splice(&membrane)?, None, Some(Isoform::Membrane)));
+        }
+    }
+    Ok(output)
+}
+
+/// Splice the given exons and translate them from the codon start of the first exon. A codon that spans a
+/// splice junction belongs to the exon that contains its last nucleotide.
+fn splice(exons: &[&(Region, usize, String)]) -> Result<Translated, GenerateError> {
+    let start = exons.first().map_or(0, |exon| exon.1);
+    let mut aa = Vec::new();
+    let mut regions: Vec<(Region, usize)> = Vec::new();
+    let mut dna = String::new();
+    let mut dna_regions: Vec<(Region, usize)> = Vec::new();
+    // The exon for every nucleotide in the spliced DNA
+    let owners = exons
+        .iter()
+        .flat_map(|exon| std::iter::repeat(exon.0).take(exon.2.len()))
+        .skip(start)
+        .collect_vec();
+    let spliced = exons.iter().map(|exon| exon.2.as_str()).collect::<String>();
+    let spliced = spliced.get(start..).unwrap_or_default();
+    for (index, codon) in spliced.as_bytes().chunks(3).enumerate() {
+        let region = owners[index * 3 + codon.len() - 1];
+        let codon =
+            std::str::from_utf8(codon).map_err(|e| GenerateError::Translation(e.to_string()))?;
+        let (_, translated) = translate(codon)?;
+        aa.extend(translated.iter().copied());
+        dna.push_str(codon);
+        match (regions.last_mut(), dna_regions.last_mut()) {
+            (Some(last), Some(last_dna)) if last.0 == region => {
+                last.1 += translated.len();
+                last_dna.1 += codon.len();
+            }
+            _ => {
+                regions.push((region, translated.len()));
+                dna_regions.push((region, codon.len()));
+            }
+        }
+    }
+    Ok((aa, regions, Vec::new(), dna, dna_regions))
+}
+
+/// Translate a C-REGION, this is only supported for chains without separate constant domains (so not for heavy chains)
+pub fn c_region(sequence: &str, chain: ChainType) -> Result<Translated, GenerateError> {
+    if chain == ChainType::Heavy {
+        return Err(GenerateError::UnsupportedLabel(
+            "C-REGION without the constant domains".to_string(),
+        ));
+    }
+    let (dna, aa) = translate(sequence)?;
+    Ok((
+        aa.clone(),
+        vec![(Region::CL, aa.len())],
+        Vec::new(),
+        dna.to_string(),
+        vec![(Region::CL, dna.len())],
+    ))
+}
+
+/// Translate an IMGT gapped V-REGION and annotate its regions and conserved residues. Codons
+/// that are (partially) gapped are left out.
+pub fn v_region(sequence: &str) -> Result<Translated, GenerateError> {
+    let mut aa = Vec::new();
+    let mut regions: Vec<(Region, usize)> = Vec::new();
+    let mut dna = String::new();
+    let mut dna_regions: Vec<(Region, usize)> = Vec::new();
+    // The index in the translated sequence for each codon (0-based) in the gapped sequence
+    let mut positions = HashMap::new();
+    for (codon_index, codon) in sequence.as_bytes().chunks(3).enumerate() {
+        if codon.len() != 3 || codon.contains(&b'.') {
+            continue;
+        }
+        let codon =
+            std::str::from_utf8(codon).map_err(|e| GenerateError::Translation(e.to_string()))?;
+        let position = codon_index * 3 + 1;
+        let Some(region) = V_REGIONS
+            .iter()
+            .find(|(_, start, end)| (*start..=*end).contains(&position))
+            .map(|(region, _, _)| *region)
+        else {
+            continue;
+        };
+        let (_, translated) = translate(codon)?;
+        positions.insert(codon_index, aa.len());
+        aa.extend(translated.iter().copied());
+        dna.push_str(codon);
+        match (regions.last_mut(), dna_regions.last_mut()) {
+            (Some(last), Some(last_dna)) if last.0 == region => {
+                last.1 += translated.len();
+                last_dna.1 += 3;
+            }
+            _ => {
+                regions.push((region, translated.len()));
+                dna_regions.push((region, 3));
+            }
+        }
+    }
+    let annotations = V_CONSERVED
+        .iter()
+        .filter_map(|(annotation, codon, expected)| {
+            positions
+                .get(&(codon - 1))
+                .filter(|index| aa.get(**index) == Some(expected))
+                .map(|index| (*annotation, *index))
+        })
+        .collect();
+    Ok((aa, regions, annotations, dna, dna_regions))
+}
+
+/// Translate a J-REGION and split it in CDR3 and FR4 at the given amino acid index, or otherwise based on
+/// the W/F-G-x-G motif. If the motif cannot be found the whole J is annotated as FR4, these are split later
+/// based on the consensus motif of the other J genes (see `split_j_genes`).
+pub fn j_region(sequence: &str, boundary: Option<usize>) -> Result<Translated, GenerateError> {
+    let (dna, aa) = translate(sequence)?;
+    let boundary = boundary.or_else(|| {
+        aa.iter().tuple_windows().position(|(a, b, _, d)| {
+            (*a == AminoAcid::W || *a == AminoAcid::F) && *b == AminoAcid::G && *d == AminoAcid::G
+        })
+    });
+    Ok(match boundary {
+        Some(boundary) if boundary > 0 && boundary < aa.len() => {
+            let dna_split = (boundary * 3).min(dna.len());
+            (
+                aa.clone(),
+                vec![(Region::CDR3, boundary), (Region::FR4, aa.len() - boundary)],
+                j_motif_annotations(&aa[boundary..], boundary),
+                dna.to_string(),
+                vec![
+                    (Region::CDR3, dna_split),
+                    (Region::FR4, dna.len() - dna_split),
+                ],
+            )
+        }
+        _ => (
+            aa.clone(),
+            vec![(Region::FR4, aa.len())],
+            Vec::new(),
+            dna.to_string(),
+            vec![(Region::FR4, dna.len())],
+        ),
+    })
+}
+
+#[cfg(test)]
+#[allow(clippy::missing_panics_doc)]
+mod tests {
+    use super::*;
+
+    /// Parse the given FASTA records and return the errors and genes
+    fn parse(fasta: &str) -> (Vec<RecordError>, Vec<(Species, SingleSeq)>) {
+        let (genes, errors): (Vec<_>, Vec<_>) =
+            parse_fasta(BufReader::new(fasta.as_bytes()), false).partition_result();
+        (errors, genes.into_iter().flatten().collect())
+    }
+
+    #[test]
+    fn heavy_constant_exons() {
+        let (errors, genes) = parse(
+            ">X1|IGHG1*01|Homo sapiens|F|CH1|1..7|7 nt|1| | | | |7+0=7| | |\ngcttccg\n\
+             >X1|IGHG1*01|Homo sapiens|F|H|8..15|8 nt|NR| | | | |8+0=8| | |\nagcccaaa\n\
+             >X1|IGHG1*01|Homo sapiens|F|CH2|16..21|6 nt|NR| | | | |6+0=6| | |\ngcacct\n\
+             >X1|IGHG1*01|Homo sapiens|F|CH3-CHS|22..36|15 nt|NR| | | | |15+0=15| | |\nggcctgccgggtaaatga\n\
+             >X1|IGHG1*01|Homo sapiens|F|CH3|22..27|6 nt|NR| | | | |6+0=6| | |\nggcctg\n\
+             >X1|IGHG1*01|Homo sapiens|F|CHS|28..36|12 nt|NR| | | | |12+0=12| | |\nccgggtaaatga\n\
+             >X1|IGHG1*01|Homo sapiens|F|M|40..48|9 nt|1| | | | |9+0=9| | |\ngagctgtaa\n\
+             >X1|IGHG1*01|Homo sapiens|F|C-REGION|1..36|36 nt|1| | | | |36+0=36| | |\ngcttccgagcccaaagcacctggcctgccgggtaaatga\n\
+             >X2|IGHG2*01|Homo sapiens|F|C-REGION|1..6|6 nt|1| | | | |6+0=6| | |\ngcttcc\n",
+        );
+        assert_eq!(errors.len(), 1);
+        assert_eq!(
+            errors[0].2,
+            GenerateError::UnsupportedLabel("C-REGION without the constant domains".to_string())
+        );
+        assert_eq!(genes.len(), 2);
+        let secreted = &genes[0].1.sequence;
+        assert_eq!(secreted.isoform, Some(Isoform::Secreted));
+        assert_eq!(secreted.sequence.to_string(), "ASEPKAPGLPGK");
+        // The codon spanning the CH1 and hinge exons belongs to the hinge
+        assert_eq!(
+            secreted.regions,
+            vec![
+                (Region::CH1, 2),
+                (Region::Hinge(1, None), 3),
+                (Region::CH2, 2),
+                (Region::CH3, 2),
+                (Region::CHS, 3)
+            ]
+        );
+        assert_eq!(secreted.dna_regions[1], (Region::Hinge(1, None), 9));
+        let membrane = &genes[1].1.sequence;
+        assert_eq!(membrane.isoform, Some(Isoform::Membrane));
+        assert_eq!(membrane.sequence.to_string(), "ASEPKAPGLEL");
+        assert_eq!(membrane.regions.last(), Some(&(Region::M, 2)));
+    }
+
+    #[test]
+    fn in_frame_pseudogene() {
+        let (errors, genes) = parse(
+            ">X3|IGKC*01|Homo sapiens|[in-frame P]|C-REGION|1..6|6 nt|1| | | | |6+0=6| | |\ngcttcc\n",
+        );
+        assert!(errors.is_empty());
+        assert_eq!(
+            genes[0].1.sequence.functionality,
+            Functionality::InFramePseudogene
+        );
+    }
+
+    #[test]
+    fn gapped_v_gene() {
+        let (errors, genes) = parse(
+            ">X07448|IGHV1-2*01|Homo sapiens|F|V-REGION|269..564|296 nt|1| | | | |296+24=320| | |\n\
+             caggtgcagctggtgcagtctggggct...gaggtgaagaagcctggggcctcagtgaaggtctcctgcaaggcttct\n\
+             ggatacaccttc............accggctactatatgcactgggtgcgacaggcccctggacaagggcttgagtgg\n\
+             atgggacggatcaaccctaacagt......ggtggcacaaactatgcacagaagtttcag...ggcagggtcaccagt\n\
+             accagggacacgtccatcagcacagcctacatggagctgagcaggctgagatctgacgacacggccgtgtattactgt\n\
+             gcgagaga\n",
+        );
+        assert!(errors.is_empty());
+        assert_eq!(genes.len(), 1);
+        let (species, gene) = &genes[0];
+        assert_eq!(*species, Species::HomoSapiens);
+        assert_eq!(gene.name.to_string(), "IGHV1-2");
+        assert_eq!((gene.allele, gene.acc.as_str()), (1, "X07448"));
+        assert_eq!(gene.sequence.functionality, Functionality::Functional);
+        assert_eq!(
+            gene.sequence.sequence.to_string(),
+            "QVQLVQSGAEVKKPGASVKVSCKASGYTFTGYYMHWVRQAPGQGLEWMGRINPNSGGTNYAQKFQGRVTSTRDTSISTAYMELSRLRSDDTAVYYCAR"
+        );
+        assert_eq!(
+            gene.sequence.regions,
+            [
+                (Region::FR1, 25),
+                (Region::CDR1, 8),
+                (Region::FR2, 17),
+                (Region::CDR2, 8),
+                (Region::FR3, 38),
+                (Region::CDR3, 2)
+            ]
+        );
+        assert_eq!(
+            gene.sequence.annotations,
+            [
+                (Annotation::Cysteine1, 21),
+                (Annotation::Tryptophan, 35),
+                (Annotation::Cysteine2, 95)
+            ]
+        );
+        assert_eq!(gene.sequence.dna.len(), 294);
+        assert_eq!(gene.sequence.dna_regions[0], (Region::FR1, 75));
+    }
+}
@@ -2,16 +2,25 @@ use std::{
     collections::HashMap,
     fmt::Display,
     fs::File,
-    io::{BufRead, BufReader, BufWriter, Write},
-    num::ParseIntError,
+    io::{BufRead, BufReader, BufWriter, Read, Write},
     ops::RangeInclusive,
+    path::PathBuf,
     str::FromStr,
 };
 
 #[path = "../../library/src/shared/mod.rs"]
 mod shared;
 
+mod airr;
+mod checksum;
+mod decompress;
+mod diff;
+mod error;
+mod fasta;
+
+use crate::error::GenerateError;
 use crate::shared::*;
+use clap::{Parser, Subcommand, ValueEnum};
 use itertools::Itertools;
 use rustyms::{
     align::Alignment,
@@ -19,32 +28,265 @@ use rustyms::{
     AminoAcid, LinearPeptide,
 };
 
+/// Generate the germline files from the IMGT LIGM-DB data
+#[derive(Parser, Debug)]
+#[command(version, about)]
+struct Cli {
+    /// The IMGT LIGM-DB data file, this can be compressed with `compress` (`imgt.dat.Z`) or gzip (`imgt.dat.gz`).
+    /// For IMGT/GENE-DB FASTA this can also be a directory, all `.fasta` files in the directory are read.
+    #[arg(short, long, default_value = concat!(env!("CARGO_MANIFEST_DIR"), "/../data/imgt.dat"))]
+    input: PathBuf,
+    /// The format of the input
+    #[arg(short, long, value_enum, default_value_t = InputFormat::Dat)]
+    format: InputFormat,
+    /// The directory to write the binary germlines, rust code, documentation, and error reports to
+    #[arg(short, long, default_value = concat!(env!("CARGO_MANIFEST_DIR"), "/../germlines"))]
+    output: PathBuf,
+    /// Only generate germlines for these species (common or scientific name, eg `Human` or `Homo sapiens`), defaults to all species
+    #[arg(short, long, value_parser = parse_species)]
+    species: Vec<Species>,
+    /// Only generate germlines for these chains (eg `H`, `K`, `L`, `A`, `B`), defaults to all chains
+    #[arg(short, long, value_parser = parse_chain)]
+    chain: Vec<ChainType>,
+    /// Only generate germlines for these kinds of genes (`V`, `D`, `J`, or `C`), defaults to all genes
+    #[arg(short, long, value_parser = parse_gene)]
+    gene: Vec<GeneType>,
+    /// Also keep partial genes, these miss their 5' and/or 3' end
+    #[arg(long)]
+    partial: bool,
+    /// How to handle regions where the translation reported by IMGT does not match the translation of the DNA
+    #[arg(long, value_enum, default_value_t = TranslationCheck::Warn)]
+    translation_check: TranslationCheck,
+    /// Also annotate the atypical N-x-C sequons as possible N linked glycans
+    #[arg(long)]
+    atypical_n_glycans: bool,
+    /// The release of the input database (eg `202345-1` for IMGT LIGM-DB), this is embedded in the generated germlines.
+    /// For AIRR germline sets this defaults to the release version given in the file.
+    #[arg(long)]
+    release: Option<String>,
+    /// The date of the release of the input database (eg `12-NOV-2023`), this is embedded in the generated germlines.
+    /// For AIRR germline sets this defaults to the release date given in the file.
+    #[arg(long)]
+    release_date: Option<String>,
+    /// Do not write the documentation (`germlines.md`)
+    #[arg(long)]
+    no_docs: bool,
+    /// Do not write the error reports (`errors.dat` and `errors.tsv`)
+    #[arg(long)]
+    no_errors: bool,
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+/// The format of the input data
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+enum InputFormat {
+    /// The IMGT/LIGM-DB EMBL flat file (`imgt.dat`)
+    Dat,
+    /// IMGT/GENE-DB reference directory FASTA files, V genes have to be IMGT gapped
+    GeneDb,
+    /// An AIRR germline set JSON file, eg from OGRDB
+    Airr,
+}
+
+/// How to handle a mismatch between the translation reported by IMGT (`/translation`) and the translation of the DNA
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+enum TranslationCheck {
+    /// Report the mismatch as a warning and use the translation of the DNA
+    Warn,
+    /// Report the mismatch as an error, the region cannot be used
+    Error,
+    /// Report the mismatch as a warning and use the translation reported by IMGT
+    PreferReported,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Compare two sets of generated germlines and write a changelog of all added, removed, renamed, and changed alleles.
+    /// Both sets have to be generated with the current layout of the germlines.
+    Diff {
+        /// The directory with the old germlines (`.bin` files)
+        old: PathBuf,
+        /// The directory with the new germlines (`.bin` files)
+        new: PathBuf,
+        /// The file to write the changelog to (TSV)
+        #[arg(short, long, default_value = "changelog.tsv")]
+        changelog: PathBuf,
+    },
+}
+
+impl Cli {
+    fn keep_species(&self, species: Species) -> bool {
+        self.species.is_empty() || self.species.contains(&species)
+    }
+
+    fn keep_gene(&self, gene: &Gene) -> bool {
+        (self.chain.is_empty() || self.chain.contains(&gene.chain))
+            && (self.gene.is_empty()
+                || self
+                    .gene
+                    .iter()
+                    .any(|g| std::mem::discriminant(g) == std::mem::discriminant(&gene.gene)))
+    }
+}
+
+/// Open the input file, or all `.fasta` files in the input directory (sorted on name) as one stream
+fn open_input(path: &std::path::Path) -> std::io::Result<Box<dyn Read>> {
+    if !path.is_dir() {
+        return Ok(Box::new(File::open(path)?));
+    }
+    let mut files = std::fs::read_dir(path)?
+        .map(|entry| entry.map(|e| e.path()))
+        .filter_ok(|path| path.extension().is_some_and(|e| e == "fasta"))
+        .collect::<Result<Vec<_>, _>>()?;
+    files.sort();
+    let mut reader: Box<dyn Read> = Box::new(std::io::empty());
+    for file in files {
+        // Separate the files with a newline, in case a file does not end in a newline
+        reader = Box::new(reader.chain(File::open(file)?).chain(&b"\n"[..]));
+    }
+    Ok(reader)
+}
+
+fn parse_species(s: &str) -> Result<Species, String> {
+    s.parse().map_err(|()| format!("Unknown species `{s}`"))
+}
+
+fn parse_chain(s: &str) -> Result<ChainType, String> {
+    s.parse().map_err(|()| format!("Unknown chain `{s}`"))
+}
+
+fn parse_gene(s: &str) -> Result<GeneType, String> {
+    match s {
+        "V" => Ok(GeneType::V),
+        "D" => Ok(GeneType::D),
+        "J" => Ok(GeneType::J),
+        "C" => Ok(GeneType::C(None)),
+        _ => Err(format!("Unknown gene `{s}`, use V, D, J, or C")),
+    }
+}
+
 fn main() {
-    let file = File::open("../data/imgt.dat")
-        .expect("Please provide the 'imgt.dat' file in the 'data' directory.");
-    let mut output = BufWriter::new(File::create("../germlines/germlines.rs").unwrap());
-    let mut docs = BufWriter::new(File::create("../germlines/germlines.md").unwrap());
-    let mut error = BufWriter::new(File::create("errors.dat").unwrap());
-    let data = parse_dat(BufReader::new(file));
+    let args = Cli::parse();
+    let result = if let Some(Command::Diff {
+        old,
+        new,
+        changelog,
+    }) = &args.command
+    {
+        diff::diff(old, new, changelog).map(|counts| {
+            for (kind, count) in counts {
+                println!("{kind}: {count}");
+            }
+        })
+    } else {
+        generate(&args)
+    };
+    if let Err(err) = result {
+        eprintln!("{err}");
+        std::process::exit(1);
+    }
+}
+
+/// Generate the germlines from the input as given on the command line
+/// # Errors
+/// If the input could not be read or the output could not be written.
+fn generate(args: &Cli) -> Result<(), String> {
+    let file = open_input(&args.input).map_err(|err| {
+        format!(
+            "Please provide the 'imgt.dat' file (possibly compressed) or a directory with IMGT/GENE-DB FASTA files, could not open '{}': {err}",
+            args.input.display()
+        )
+    })?;
+    let (file, checksum) = checksum::hashing_reader(file);
+    let file = decompress::decompress(file)
+        .map_err(|err| format!("Could not read '{}': {err}", args.input.display()))?;
+    let write_error =
+        |err: std::io::Error| format!("Could not write to '{}': {err}", args.output.display());
+    std::fs::create_dir_all(&args.output).map_err(write_error)?;
+    let mut output =
+        BufWriter::new(File::create(args.output.join("germlines.rs")).map_err(write_error)?);
+    let mut docs = if args.no_docs {
+        None
+    } else {
+        Some(BufWriter::new(
+            File::create(args.output.join("germlines.md")).map_err(write_error)?,
+        ))
+    };
+    let mut release = None;
     let mut grouped = HashMap::new();
     let mut errors = Vec::new();
+    let mut entry_errors = Vec::new();
+    let mut warnings = Vec::new();
     let mut temp: Vec<(Species, SingleSeq)> = Vec::new();
-    for element in data.flatten() {
-        let species = element.species;
-        // if species != Species::HomoSapiens {
-        //     continue;
-        // }
-        for gene in element.genes {
-            match gene.clone().finish() {
-                Ok(gene) => temp.push((species, gene)),
-                Err(err) => {
-                    errors.push((species, gene, err));
+    match args.format {
+        InputFormat::Dat => {
+            let data = parse_dat(BufReader::new(file), args.partial, args.translation_check);
+            for element in data {
+                let element = match element {
+                    Ok(element) => element,
+                    Err(err) => {
+                        if err.0.map_or(true, |species| args.keep_species(species)) {
+                            entry_errors.push(err);
+                        }
+                        continue;
+                    }
+                };
+                let species = element.species;
+                if !args.keep_species(species) {
+                    continue;
+                }
+                warnings.extend(
+                    element
+                        .warnings
+                        .into_iter()
+                        .map(|(key, warning)| (species, format!("{}\t{key}", element.id), warning)),
+                );
+                for gene in element.genes {
+                    match gene.clone().finish() {
+                        Ok(genes) => temp.extend(
+                            genes
+                                .into_iter()
+                                .filter(|gene| args.keep_gene(&gene.name))
+                                .map(|gene| (species, gene)),
+                        ),
+                        Err(err) => {
+                            if Gene::from_imgt_name_with_allele(&gene.allele)
+                                .map_or(true, |(name, _)| args.keep_gene(&name))
+                            {
+                                errors.push((species, gene, err));
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        format => {
+            let records: Box<dyn Iterator<Item = _>> = if format == InputFormat::GeneDb {
+                Box::new(fasta::parse_fasta(BufReader::new(file), args.partial))
+            } else {
+                Box::new(airr::parse_airr(BufReader::new(file), &mut release)?)
+            };
+            for record in records {
+                match record {
+                    Ok(genes) => temp.extend(genes.into_iter().filter(|(species, gene)| {
+                        args.keep_species(*species) && args.keep_gene(&gene.name)
+                    })),
+                    Err(err) => {
+                        if err.0.map_or(true, |species| args.keep_species(species)) {
+                            entry_errors.push(err);
+                        }
+                    }
                 }
             }
         }
-        //writeln!(output, "{}", element.unwrap()).unwrap();
     }
 
+    split_j_genes(&mut temp);
+    annotate_allotypes(&mut temp);
+    annotate_glycans(&mut temp, args.atypical_n_glycans);
+    annotate_disulfides(&mut temp);
+
     // Combine temp seqs
     let mut deduped_temp: Vec<(Species, TemporaryGermline)> = Vec::new();
     'sequences: for (species, seq) in temp {
@@ -59,120 +301,221 @@ fn main() {
             species,
             TemporaryGermline {
                 name: seq.name.clone(),
-                alleles: vec![(seq.allele, vec![TemporarySequence::from_single(seq)])],
+                alleles: vec![(
+                    (seq.allele, seq.sequence.reading_frame, seq.sequence.isoform),
+                    vec![TemporarySequence::from_single(seq)],
+                )],
             },
         ))
     }
 
-    // Save temp seqs in final data structure
+    // Validate and save temp seqs in final data structure, any invalid sequences are left out
+    let mut invalid = Vec::new();
     for (species, entry) in deduped_temp {
-        if species == Species::HomoSapiens
-            && entry.name.gene == GeneType::C(Some(Constant::A))
-            && entry.name.chain == ChainType::Heavy
-        {
-            println!("{}", entry);
+        let mut germline = entry.finalise();
+        for (allele, seq) in &mut germline.alleles {
+            let name = format!("{}*{allele:02}", germline.name);
+            seq.alternatives
+                .retain(|alternative| is_valid(species, &name, alternative, &mut invalid));
         }
-        grouped
-            .entry(species)
-            .or_insert(Germlines::new(species))
-            .insert(entry.finalise())
-    }
-
-    // Keep track of all errors
-    for (species, errors) in errors
-        .iter()
-        .map(|(species, gene, err)| (species, (gene, err)))
-        .into_group_map()
-        .into_iter()
-        .map(|(species, genes)| {
-            (
+        germline.alleles.retain(|(allele, seq)| {
+            is_valid(
                 species,
-                genes
-                    .into_iter()
-                    .map(|(gene, err)| (gene.key.clone(), (gene, err)))
-                    .into_group_map(),
+                &format!("{}*{allele:02}", germline.name),
+                seq,
+                &mut invalid,
             )
-        })
-    {
-        writeln!(error, "SPECIES: {species}").unwrap();
-        for (gene, errors) in errors {
-            writeln!(error, "GENE: {gene}").unwrap();
-            for (gene, err) in errors {
-                writeln!(error, "ERROR FOR GENE:\n{species}\t{gene}\t{err}\n").unwrap();
+        });
+        if !germline.alleles.is_empty() {
+            grouped
+                .entry(species)
+                .or_insert(Germlines::new(species))
+                .insert(germline);
+        }
+    }
+
+    if !args.no_errors {
+        // Keep track of all errors
+        let mut error =
+            BufWriter::new(File::create(args.output.join("errors.dat")).map_err(write_error)?);
+        for (species, errors) in errors
+            .iter()
+            .map(|(species, gene, err)| (species, (gene, err)))
+            .into_group_map()
+            .into_iter()
+            .map(|(species, genes)| {
+                (
+                    species,
+                    genes
+                        .into_iter()
+                        .map(|(gene, err)| (gene.key.clone(), (gene, err)))
+                        .into_group_map(),
+                )
+            })
+        {
+            writeln!(error, "SPECIES: {species}").map_err(write_error)?;
+            for (gene, errors) in errors {
+                writeln!(error, "GENE: {gene}").map_err(write_error)?;
+                for (gene, err) in errors {
+                    writeln!(error, "ERROR FOR GENE:\n{species}\t{gene}\t{err}\n")
+                        .map_err(write_error)?;
+                }
             }
         }
+        for (species, id, err) in &entry_errors {
+            writeln!(
+                error,
+                "ERROR FOR ENTRY:\n{}\t{id}\t{err}\n",
+                species.map_or("-".to_string(), |s| s.to_string())
+            )
+            .map_err(write_error)?;
+        }
+        for (species, name, err) in &invalid {
+            writeln!(error, "INVALID ALLELE:\n{species}\t{name}\t{err}\n").map_err(write_error)?;
+        }
+        for (species, region, warning) in &warnings {
+            writeln!(
+                error,
+                "WARNING FOR REGION:\n{species}\t{region}\t{warning}\n"
+            )
+            .map_err(write_error)?;
+        }
+
+        // Statistics on the lost entries and genes per species and category
+        let mut statistics = errors
+            .iter()
+            .map(|(species, gene, err)| {
+                (species.scientific_name(), gene.key.as_str(), err.category())
+            })
+            .chain(entry_errors.iter().map(|(species, _, err)| {
+                (
+                    species.map_or("-", |s| s.scientific_name()),
+                    "entry",
+                    err.category(),
+                )
+            }))
+            .chain(
+                invalid
+                    .iter()
+                    .map(|(species, _, err)| (species.scientific_name(), "allele", err.category())),
+            )
+            .chain(warnings.iter().map(|(species, _, warning)| {
+                (species.scientific_name(), "warning", warning.category())
+            }))
+            .counts()
+            .into_iter()
+            .collect_vec();
+        statistics.sort_unstable();
+        let mut report =
+            BufWriter::new(File::create(args.output.join("errors.tsv")).map_err(write_error)?);
+        writeln!(report, "species\tkind\tcategory\tcount").map_err(write_error)?;
+        for ((species, kind, category), count) in statistics {
+            writeln!(report, "{species}\t{kind}\t{category}\t{count}").map_err(write_error)?;
+        }
     }
 
     writeln!(
         output,
-        "#![allow(non_snake_case,non_upper_case_globals)]\nuse std::sync::OnceLock;\nuse crate::shared::{{Germlines, Species}};"
+        "#![allow(non_snake_case,non_upper_case_globals)]\nuse std::sync::OnceLock;\nuse crate::shared::{{Germlines, Species}};\nuse crate::DatabaseInfo;"
     )
-    .unwrap();
-    writeln!(output, "/// Get the germlines for any of the available species. See the main documentation for which species have which data available.").unwrap();
+    .map_err(write_error)?;
+    // The release given on the command line takes precedence over the release given in the input
+    let mut release = release.unwrap_or_default();
+    if let Some(version) = &args.release {
+        release.release.clone_from(version);
+    }
+    if let Some(date) = &args.release_date {
+        release.date.clone_from(date);
+    }
+    writeln!(
+        output,
+        "/// The database release these germlines were generated from\npub const DATABASE_INFO: DatabaseInfo = DatabaseInfo {{release: {:?}, date: {:?}, generator_version: {:?}, checksum: {:?}}};",
+        release.release,
+        release.date,
+        env!("CARGO_PKG_VERSION"),
+        checksum.hex(),
+    )
+    .map_err(write_error)?;
+    writeln!(output, "/// Get the germlines for any of the available species. See the main documentation for which species have which data available.").map_err(write_error)?;
     let mut found_species = Vec::new();
     let mut found_germlines: Vec<(Species, Germlines)> = grouped.into_iter().collect();
     found_germlines.sort_unstable_by_key(|g| g.0);
     for (species, germlines) in found_germlines {
-        writeln!(
-            docs,
-            "## {} / {}
+        if let Some(docs) = &mut docs {
+            writeln!(
+                docs,
+                "## {} / {}
 
-| Kind | V | J | C |
-|------|---|---|---|
+| Kind | V | D | J | C |
+|------|---|---|---|---|
 |IGHV{}
 |IGKV{}
 |IGLV{}
 |IGIV{}
+|TRAV{}
+|TRBV{}
+|TRGV{}
+|TRDV{}
 
 _Number of genes / number of alleles_
 ",
-            species.scientific_name(),
-            species.common_name(),
-            germlines.h.doc_row(),
-            germlines.k.doc_row(),
-            germlines.l.doc_row(),
-            germlines.i.doc_row(),
-        )
-        .unwrap();
+                species.scientific_name(),
+                species.common_name(),
+                germlines.h.doc_row(),
+                germlines.k.doc_row(),
+                germlines.l.doc_row(),
+                germlines.i.doc_row(),
+                germlines.a.doc_row(),
+                germlines.b.doc_row(),
+                germlines.g.doc_row(),
+                germlines.d.doc_row(),
+            )
+            .map_err(write_error)?;
+        }
         found_species.push(species);
 
-        let mut file = std::fs::File::create(format!("../germlines/{species}.bin")).unwrap();
-        file.write_all(&bincode::serialize::<Germlines>(&germlines).unwrap())
-            .unwrap();
+        let mut file =
+            File::create(args.output.join(format!("{species}.bin"))).map_err(write_error)?;
+        file.write_all(
+            &bincode::serialize::<Germlines>(&germlines)
+                .map_err(|err| format!("Could not serialise the germlines of {species}: {err}"))?,
+        )
+        .map_err(write_error)?;
     }
     // germlines
     writeln!(
         output,
         "pub fn germlines(species: Species) -> Option<&'static Germlines> {{match species {{"
     )
-    .unwrap();
+    .map_err(write_error)?;
 
     for species in &found_species {
-        writeln!(output, "Species::{0} => Some(lock_{0}()),", species.ident()).unwrap();
+        writeln!(output, "Species::{0} => Some(lock_{0}()),", species.ident())
+            .map_err(write_error)?;
     }
-    writeln!(output, "_=>None}}}}").unwrap();
+    writeln!(output, "_=>None}}}}").map_err(write_error)?;
     // all_germlines
     writeln!(
         output,
 "/// Get all germlines in one iterator, see the main documentation for more information about the available germlines
 pub fn all_germlines() -> impl std::iter::Iterator<Item = &'static Germlines> {{"
     )
-    .unwrap();
+    .map_err(write_error)?;
     let mut first = true;
     for species in &found_species {
         if first {
             first = false;
-            writeln!(output, "std::iter::once(lock_{}())", species.ident()).unwrap();
+            writeln!(output, "std::iter::once(lock_{}())", species.ident()).map_err(write_error)?;
         } else {
             writeln!(
                 output,
                 ".chain(std::iter::once(lock_{}()))",
                 species.ident()
             )
-            .unwrap();
+            .map_err(write_error)?;
         }
     }
-    writeln!(output, "}}").unwrap();
+    writeln!(output, "}}").map_err(write_error)?;
     // par_germlines
     writeln!(
         output,
@@ -182,22 +525,23 @@ use rayon::prelude::*;
 #[cfg(feature = \"rayon\")]
 pub fn par_germlines() -> impl rayon::prelude::ParallelIterator<Item = &'static Germlines> {{"
     )
-    .unwrap();
+    .map_err(write_error)?;
     let mut first = true;
     for species in &found_species {
         if first {
             first = false;
-            writeln!(output, "rayon::iter::once(lock_{}())", species.ident()).unwrap();
+            writeln!(output, "rayon::iter::once(lock_{}())", species.ident())
+                .map_err(write_error)?;
         } else {
             writeln!(
                 output,
                 ".chain(rayon::iter::once(lock_{}()))",
                 species.ident()
             )
-            .unwrap();
+            .map_err(write_error)?;
         }
     }
-    writeln!(output, "}}").unwrap();
+    writeln!(output, "}}").map_err(write_error)?;
 
     for species in &found_species {
         writeln!(
@@ -206,16 +550,36 @@ pub fn par_germlines() -> impl rayon::prelude::ParallelIterator<Item = &'static
 fn lock_{0}()->&'static Germlines{{LOCK_{0}.get_or_init(|| {{bincode::deserialize(include_bytes!(\"{species}.bin\")).unwrap()}})}}",
             species.ident(),
         )
-        .unwrap();
+        .map_err(write_error)?;
+    }
+    Ok(())
+}
+
+/// The release of the input database
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct Release {
+    release: String,
+    date: String,
+}
+
+impl Default for Release {
+    fn default() -> Self {
+        Self {
+            release: "unknown".to_string(),
+            date: "unknown".to_string(),
+        }
     }
 }
 
+/// Parse the IMGT data file
 fn parse_dat<T: std::io::Read>(
     reader: BufReader<T>,
-) -> impl Iterator<Item = Result<DataItem, String>> {
+    keep_partial: bool,
+    translation_check: TranslationCheck,
+) -> impl Iterator<Item = Result<DataItem, (Option<Species>, String, GenerateError)>> {
     reader
         .lines()
-        .batching(|f| {
+        .batching(move |f| {
             let mut data = PreDataItem::default();
             let mut next = f.next();
             while let Some(Ok(line)) = next {
@@ -234,8 +598,9 @@ fn parse_dat<T: std::io::Read>(
                     } else if line.starts_with("FT") {
                         data.ft.push(line);
                     } else if line.starts_with("OS") && data.os.is_none() {
-                        data.os = Species::from_imgt(line[5..].trim()).unwrap_or_else(|()| {
-                            println!("Not a species name: `{line}`");
+                        let name = line[5..].trim();
+                        data.os = Species::from_imgt(name).unwrap_or_else(|()| {
+                            data.unknown_os = Some(name.to_string());
                             None
                         });
                     } else if line.starts_with("  ") {
@@ -251,12 +616,18 @@ fn parse_dat<T: std::io::Read>(
             None
         })
         .filter(|pre| {
-            pre.kw.contains(&"immunoglobulin (IG)".to_string())
+            (pre.kw.contains(&"immunoglobulin (IG)".to_string())
+                || pre.kw.contains(&"T cell receptor (TR)".to_string()))
                 && (pre.kw.contains(&"functional".to_string())
+                    || pre.kw.contains(&"ORF".to_string())
+                    || pre.kw.contains(&"pseudogene".to_string())
                     || pre.kw.contains(&"germline".to_string()))
-                && pre.os.is_some()
+                && (pre.os.is_some() || pre.unknown_os.is_some())
+        })
+        .map(move |pre| {
+            let (species, id) = (pre.os, pre.id.clone());
+            DataItem::new(pre, keep_partial, translation_check).map_err(|err| (species, id, err))
         })
-        .map(DataItem::new)
 }
 
 #[derive(Default, Debug)]
@@ -266,6 +637,8 @@ struct PreDataItem {
     ft_key_width: usize,
     ft: Vec<String>,
     os: Option<Species>,
+    /// The species name if it is not recognised, these entries are reported as an error
+    unknown_os: Option<String>,
     sq: String,
 }
 
@@ -276,6 +649,10 @@ struct DataItem {
     regions: Vec<Region>,
     species: Species,
     sequence: String,
+    keep_partial: bool,
+    translation_check: TranslationCheck,
+    /// Problems with regions that did not prevent the region from being used, with the key of the region
+    warnings: Vec<(String, GenerateError)>,
 }
 
 #[derive(Clone, Debug, Eq, PartialEq)]
@@ -284,6 +661,10 @@ struct IMGTGene {
     key: String,
     location: Location,
     allele: String,
+    /// The functionality as annotated by IMGT, genes without it are reported as an error
+    functionality: Option<Functionality>,
+    partial: bool,
+    truncation: Truncation,
     regions: HashMap<String, Region>,
 }
 
@@ -293,12 +674,55 @@ struct Region {
     key: String,
     location: Location,
     reported_seq: String,
-    found_seq: Result<(String, AASequence), String>,
+    /// The DNA and its translation, None until the region is loaded from the entry
+    found_seq: Option<Result<(String, AASequence), GenerateError>>,
     allele: String,
-    functional: bool,
+    functionality: Option<Functionality>,
     partial: bool,
+    truncation: Truncation,
     shift: usize,
     splice_aa: Option<AminoAcid>,
+    /// The nucleotides of the codon spanning the splice junction at the start of this region
+    junction_dna: String,
+}
+
+impl Region {
+    /// Get the amino acid sequence, location, and DNA of this region. If the first codon spans the splice
+    /// junction its expected amino acid is prepended, with the nucleotides of that codon in this region.
+    fn section(&self) -> Result<Section, GenerateError> {
+        self.found_seq
+            .as_ref()
+            .ok_or_else(|| GenerateError::MissingRegion(self.key.clone()))?
+            .as_ref()
+            .map(|seq| {
+                let spliced = self.splice_aa.filter(|_| self.shift != 2);
+                let mut aa = spliced.map(|aa| vec![aa]).unwrap_or_default();
+                aa.extend(seq.1 .0.iter().copied());
+                let dna = if spliced.is_some() {
+                    format!("{}{}", self.junction_dna, seq.0)
+                } else {
+                    seq.0.clone()
+                };
+                (aa, self.location.clone(), dna)
+            })
+            .map_err(Clone::clone)
+    }
+
+    /// Get the index of the amino acid that contains the start of the inner location in the translated
+    /// sequence of this region (including the spliced amino acid if present)
+    fn aa_index(&self, inner: &Location) -> Option<usize> {
+        if !self.location.contains(inner) {
+            return None;
+        }
+        let offset = self.location.spliced_offset(inner.first()?)?;
+        let (inner_shift, shift) = if self.shift == 2 {
+            (1, 0)
+        } else {
+            (0, self.shift)
+        };
+        let spliced = usize::from(self.splice_aa.is_some() && self.shift != 2);
+        Some((offset + inner_shift).checked_sub(shift)? / 3 + spliced)
+    }
 }
 
 #[derive(Clone, Debug, Hash, Eq, PartialEq)]
@@ -307,64 +731,131 @@ enum Location {
     Complement(RangeInclusive<usize>),
     SingleNormal(usize),
     SingleComplement(usize),
+    /// Multiple ranges that are spliced together, sorted on position
+    Join(Vec<RangeInclusive<usize>>),
+    /// Multiple ranges on the complement strand that are spliced together, sorted on position
+    ComplementJoin(Vec<RangeInclusive<usize>>),
 }
 
 impl Location {
+    /// Get all ranges for this location sorted on position, and if these are on the complement strand
+    fn ranges(&self) -> (Vec<RangeInclusive<usize>>, bool) {
+        match self {
+            Self::Normal(range) => (vec![range.clone()], false),
+            Self::Complement(range) => (vec![range.clone()], true),
+            Self::SingleNormal(index) => (vec![*index..=*index], false),
+            Self::SingleComplement(index) => (vec![*index..=*index], true),
+            Self::Join(ranges) => (ranges.clone(), false),
+            Self::ComplementJoin(ranges) => (ranges.clone(), true),
+        }
+    }
+
+    /// Build a location from a list of ranges (sorted on position)
+    fn from_ranges(ranges: Vec<RangeInclusive<usize>>, complement: bool) -> Self {
+        match (ranges.len(), complement) {
+            (1, false) => Self::Normal(ranges[0].clone()),
+            (1, true) => Self::Complement(ranges[0].clone()),
+            (_, false) => Self::Join(ranges),
+            (_, true) => Self::ComplementJoin(ranges),
+        }
+    }
+
+    /// Get the first position of this location in reading direction
+    fn first(&self) -> Option<usize> {
+        let (ranges, complement) = self.ranges();
+        if complement {
+            ranges.last().map(|r| *r.end())
+        } else {
+            ranges.first().map(|r| *r.start())
+        }
+    }
+
     fn contains(&self, other: &Location) -> bool {
-        match (self, other) {
-            (Self::Complement(s), Self::Complement(o)) | (Self::Normal(s), Self::Normal(o)) => {
-                s.start() <= o.start() && s.end() >= o.end()
+        let (ranges, complement) = self.ranges();
+        let (other_ranges, other_complement) = other.ranges();
+        complement == other_complement
+            && other_ranges.iter().all(|o| {
+                ranges
+                    .iter()
+                    .any(|s| s.start() <= o.start() && s.end() >= o.end())
+            })
+    }
+
+    /// Get the offset in nucleotides of the given position in the spliced sequence, following the reading direction
+    fn spliced_offset(&self, position: usize) -> Option<usize> {
+        let (mut ranges, complement) = self.ranges();
+        if complement {
+            ranges.reverse();
+        }
+        let mut offset = 0;
+        for range in ranges {
+            if range.contains(&position) {
+                return Some(
+                    offset
+                        + if complement {
+                            range.end() - position
+                        } else {
+                            position - range.start()
+                        },
+                );
             }
-            (Self::Complement(s), Self::SingleComplement(o)) => s.contains(o),
-            (Self::Normal(s), Self::SingleNormal(o)) => s.contains(o),
-            _ => false,
+            offset += range.end() - range.start() + 1;
         }
+        None
     }
 
     fn get_aa_loc(&self, inner: &Self) -> Option<RangeInclusive<usize>> {
         if !self.contains(inner) {
             None
         } else {
-            match (self, inner) {
-                (Self::Complement(s), Self::Complement(o)) | (Self::Normal(s), Self::Normal(o)) => {
-                    Some((o.start() - s.start()) / 3..=(o.end() - s.start()) / 3)
-                }
-                (Self::Normal(s), Self::SingleNormal(o))
-                | (Self::Complement(s), Self::SingleComplement(o)) => {
-                    Some((o - s.start()) / 3..=(o - s.start()) / 3)
-                }
-                _ => None,
-            }
+            let (ranges, complement) = inner.ranges();
+            let (first, last) = if complement {
+                (*ranges.last()?.end(), *ranges.first()?.start())
+            } else {
+                (*ranges.first()?.start(), *ranges.last()?.end())
+            };
+            Some(self.spliced_offset(first)? / 3..=self.spliced_offset(last)? / 3)
         }
     }
 
-    /// Break the location around the given amino acid index in the location. If the position is outside the range or this location is a single it returns None.
+    /// Break the location around the given amino acid index in the location. If the position is outside the range or either side would be empty it returns None.
     fn splice(&self, position: usize) -> Option<(Self, Self)> {
-        match self {
-            Self::Normal(s) => {
-                let mid_point = *s.start() + position * 3;
-                if mid_point >= *s.end() {
-                    None
-                } else {
-                    Some((
-                        Self::Complement((*s.start())..=mid_point),
-                        Self::Complement(mid_point..=*s.end()),
-                    ))
-                }
-            }
-            Self::Complement(s) => {
-                let mid_point = *s.end() - position * 3;
-                if mid_point <= *s.start() {
-                    None
+        let (mut ranges, complement) = self.ranges();
+        if complement {
+            ranges.reverse();
+        }
+        let mut left = position * 3;
+        let mut first = Vec::new();
+        let mut second = Vec::new();
+        for range in ranges {
+            let length = range.end() - range.start() + 1;
+            if left >= length {
+                first.push(range);
+                left -= length;
+            } else if left == 0 {
+                second.push(range);
+            } else {
+                if complement {
+                    first.push(range.end() - left + 1..=*range.end());
+                    second.push(*range.start()..=range.end() - left);
                 } else {
-                    Some((
-                        Self::Complement((*s.start())..=mid_point),
-                        Self::Complement(mid_point..=*s.end()),
-                    ))
+                    first.push(*range.start()..=range.start() + left - 1);
+                    second.push(range.start() + left..=*range.end());
                 }
+                left = 0;
             }
-            _ => None,
         }
+        if first.is_empty() || second.is_empty() {
+            return None;
+        }
+        if complement {
+            first.reverse();
+            second.reverse();
+        }
+        Some((
+            Self::from_ranges(first, complement),
+            Self::from_ranges(second, complement),
+        ))
     }
 }
 
@@ -375,83 +866,186 @@ impl Display for Location {
             Self::Normal(range) => write!(f, "{}..{}", range.start(), range.end()),
             Self::SingleComplement(loc) => write!(f, "c{}", loc),
             Self::SingleNormal(loc) => write!(f, "{}", loc),
+            Self::Join(ranges) => write!(
+                f,
+                "join({})",
+                ranges
+                    .iter()
+                    .map(|r| format!("{}..{}", r.start(), r.end()))
+                    .join(",")
+            ),
+            Self::ComplementJoin(ranges) => write!(
+                f,
+                "cjoin({})",
+                ranges
+                    .iter()
+                    .map(|r| format!("{}..{}", r.start(), r.end()))
+                    .join(",")
+            ),
+        }
+    }
+}
+
+/// Determine the missing ends of a location, a '<' marks a partial lower bound and a '>' a partial upper bound
+fn truncation(location: &str) -> Truncation {
+    let complement = location.starts_with("complement(") || location.contains("(complement(");
+    let (lower, upper) = (location.contains('<'), location.contains('>'));
+    if complement {
+        Truncation {
+            five_prime: upper,
+            three_prime: lower,
+        }
+    } else {
+        Truncation {
+            five_prime: lower,
+            three_prime: upper,
         }
     }
 }
 
 impl FromStr for Location {
-    type Err = ParseIntError;
+    type Err = String;
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        if let Some(tail) = s.strip_prefix("complement(") {
-            tail.trim_end_matches(')')
-                .split_once("..")
-                .map(|(start, end)| {
-                    Ok(Self::Complement(
-                        start.trim_start_matches('<').parse::<usize>()? - 1
-                            ..=end.trim_start_matches('>').parse::<usize>()? - 1,
-                    ))
+        /// Parse a single range or position (1 based inclusive) into a 0 based inclusive range
+        fn parse_range(s: &str) -> Result<RangeInclusive<usize>, String> {
+            let parse = |n: &str| {
+                n.trim_start_matches(['<', '>'])
+                    .parse::<usize>()
+                    .map_err(|e| format!("Invalid position `{n}`: {e}"))
+                    .and_then(|n| n.checked_sub(1).ok_or(format!("Invalid position `{n}`")))
+            };
+            s.split_once("..").map_or_else(
+                || parse(s).map(|n| n..=n),
+                |(start, end)| Ok(parse(start)?..=parse(end)?),
+            )
+        }
+
+        fn parse_ranges(s: &str) -> Result<(Vec<RangeInclusive<usize>>, bool), String> {
+            let mut complement = None;
+            let mut ranges = s
+                .split(',')
+                .map(|part| {
+                    let part = part.trim();
+                    let (part, part_complement) = part
+                        .strip_prefix("complement(")
+                        .map_or((part, false), |p| (p.trim_end_matches(')'), true));
+                    if *complement.get_or_insert(part_complement) != part_complement {
+                        return Err(format!("Mixed strands in joined location `{s}`"));
+                    }
+                    parse_range(part)
                 })
-                .unwrap_or_else(|| Ok(Self::SingleComplement(tail.trim_end_matches(')').parse()?)))
+                .collect::<Result<Vec<_>, _>>()?;
+            ranges.sort_unstable_by_key(|r| *r.start());
+            Ok((ranges, complement.unwrap_or_default()))
+        }
+
+        let s = s.trim();
+        if let Some(tail) = s.strip_prefix("complement(join(") {
+            let (ranges, inner_complement) = parse_ranges(tail.trim_end_matches(')'))?;
+            if inner_complement {
+                return Err(format!("Double complement in location `{s}`"));
+            }
+            Ok(Self::from_ranges(ranges, true))
+        } else if let Some(tail) = s.strip_prefix("join(") {
+            let (ranges, complement) = parse_ranges(
+                tail.strip_suffix(')')
+                    .ok_or(format!("Unclosed joined location `{s}`"))?,
+            )?;
+            Ok(Self::from_ranges(ranges, complement))
+        } else if let Some(tail) = s.strip_prefix("complement(") {
+            let tail = tail.trim_end_matches(')');
+            if tail.contains("..") {
+                Ok(Self::Complement(parse_range(tail)?))
+            } else {
+                Ok(Self::SingleComplement(*parse_range(tail)?.start()))
+            }
+        } else if s.contains("..") {
+            Ok(Self::Normal(parse_range(s)?))
         } else {
-            s.split_once("..")
-                .map(|(start, end)| {
-                    Ok(Self::Normal(
-                        start.trim_start_matches('<').parse::<usize>()? - 1
-                            ..=end.trim_start_matches('>').parse::<usize>()? - 1,
-                    ))
-                })
-                .unwrap_or_else(|| Ok(Self::SingleNormal(s.parse()?)))
+            Ok(Self::SingleNormal(*parse_range(s)?.start()))
         }
     }
 }
 
 impl DataItem {
-    pub fn new(data: PreDataItem) -> Result<Self, String> {
+    pub fn new(
+        data: PreDataItem,
+        keep_partial: bool,
+        translation_check: TranslationCheck,
+    ) -> Result<Self, GenerateError> {
         let mut result = Self {
             id: data.id[5..].split(';').next().unwrap().to_string(),
-            species: data.os.ok_or("No species found")?,
+            species: data.os.ok_or_else(|| {
+                data.unknown_os
+                    .clone()
+                    .map_or(GenerateError::MissingSpecies, GenerateError::UnknownSpecies)
+            })?,
             sequence: data.sq,
             genes: Vec::new(),
             regions: Vec::new(),
+            keep_partial,
+            translation_check,
+            warnings: Vec::new(),
+        };
+        let id = result.id.clone();
+        let new_region = |key: &str, location: &str| -> Result<Region, GenerateError> {
+            if location.contains('^') {
+                return Err(GenerateError::UnsupportedLocation(location.to_string()));
+            }
+            Ok(Region {
+                acc: id.clone(),
+                key: key.to_string(),
+                location: location.parse().map_err(GenerateError::InvalidLocation)?,
+                reported_seq: String::new(),
+                found_seq: None,
+                allele: String::new(),
+                functionality: None,
+                partial: false,
+                truncation: truncation(location),
+                shift: 0,
+                splice_aa: None,
+                junction_dna: String::new(),
+            })
         };
+        let is_complete =
+            |location: &str| location.matches('(').count() == location.matches(')').count();
         let mut current: Option<Region> = None;
+        // A location that is split over multiple lines (long joined locations) with its key
+        let mut partial_location: Option<(String, String)> = None;
         let mut sequence = false;
         for line in data.ft {
             let line = &line[5..];
+            if let Some((key, mut location)) = partial_location.take() {
+                location.push_str(line.trim());
+                if is_complete(&location) {
+                    current = Some(new_region(&key, &location)?);
+                } else {
+                    partial_location = Some((key, location));
+                }
+                continue;
+            }
             if !line.starts_with(' ') || current.is_none() {
-                if let Some(region) = current {
+                if let Some(region) = current.take() {
                     result.add_region(region);
                 }
-                let (key, location) = (&line[..data.ft_key_width], &line[data.ft_key_width..]);
-                if location.contains("join") {
-                    return Err("Location is a joined region".to_string());
-                }
-                if location.contains('^') {
-                    return Err("Location is a ^ region".to_string());
+                let (key, location) = (
+                    line[..data.ft_key_width].trim(),
+                    line[data.ft_key_width..].trim(),
+                );
+                if is_complete(location) {
+                    current = Some(new_region(key, location)?);
+                } else {
+                    partial_location = Some((key.to_string(), location.to_string()));
                 }
-                let location = location
-                    .trim()
-                    .parse()
-                    .unwrap_or_else(|_| panic!("`{}` not a valid location", location));
-                current = Some(Region {
-                    acc: result.id.clone(),
-                    key: key.trim().to_string(),
-                    location,
-                    reported_seq: String::new(),
-                    found_seq: Err("Not loaded".to_string()),
-                    allele: String::new(),
-                    functional: false,
-                    partial: false,
-                    shift: 0,
-                    splice_aa: None,
-                });
                 continue;
             }
             if let Some(current) = &mut current {
                 let trimmed = line.trim();
                 let lowercase = trimmed.to_lowercase();
                 if sequence {
-                    current.reported_seq = trimmed.trim_end_matches('\"').to_string();
+                    current
+                        .reported_seq
+                        .push_str(trimmed.trim_end_matches('\"'));
                     if trimmed.ends_with('\"') {
                         sequence = false;
                     }
@@ -465,7 +1059,7 @@ impl DataItem {
                 } else if let Some(tail) = trimmed.strip_prefix("/codon_start=") {
                     current.shift = tail
                         .parse::<usize>()
-                        .map_err(|_| format!("Not a valid codon_start: '{tail}'"))?
+                        .map_err(|_| GenerateError::InvalidCodonStart(tail.to_string()))?
                         - 1;
                 } else if let Some(tail) = trimmed.strip_prefix("/splice-expectedcodon=") {
                     if let Some(i) = tail.find(']') {
@@ -475,7 +1069,31 @@ impl DataItem {
                     || lowercase.starts_with("/note=\"functional\"")
                     || lowercase.starts_with("/imgt_note=\"functional\"")
                 {
-                    current.functional = true;
+                    current.functionality = Some(Functionality::Functional);
+                } else if lowercase.starts_with("/orf")
+                    || lowercase.starts_with("/note=\"orf\"")
+                    || lowercase.starts_with("/imgt_note=\"orf\"")
+                {
+                    current.functionality = Some(Functionality::OpenReadingFrame);
+                } else if lowercase.starts_with("/pseudo")
+                    || ["/note=\"", "/imgt_note=\""].iter().any(|prefix| {
+                        lowercase.strip_prefix(prefix).is_some_and(|note| {
+                            ["pseudogene", "in-frame pseudogene", "in frame pseudogene"]
+                                .iter()
+                                .any(|value| note.starts_with(value))
+                        })
+                    })
+                {
+                    current.functionality = Some(
+                        if current.functionality == Some(Functionality::InFramePseudogene)
+                            || lowercase.contains("in-frame")
+                            || lowercase.contains("in frame")
+                        {
+                            Functionality::InFramePseudogene
+                        } else {
+                            Functionality::Pseudogene
+                        },
+                    );
                 } else if trimmed.starts_with("/partial") {
                     current.partial = true;
                 }
@@ -490,19 +1108,29 @@ impl DataItem {
 
     fn add_region(&mut self, mut region: Region) {
         // Get the actual sequence
-        region.found_seq = self.get_sequence(&region.location, region.shift);
+        region.found_seq = Some(self.get_sequence(&region.location, region.shift));
+        if region.splice_aa.is_some() && region.shift == 1 {
+            region.junction_dna = self
+                .get_dna(&region.location, 0)
+                .ok()
+                .and_then(|dna| dna.get(..region.shift).map(ToString::to_string))
+                .unwrap_or_default();
+        }
+        self.check_translation(&mut region);
 
         // Determine if what this region is and if is warrants keeping
-        if ["V-GENE", "C-GENE", "J-GENE"].contains(&region.key.as_str()) // , "D-GENE"
-            && region.functional
-            && !region.partial
-            && region.allele.starts_with("IG")
+        if ["V-GENE", "C-GENE", "J-GENE", "D-GENE"].contains(&region.key.as_str())
+            && (!region.partial || self.keep_partial)
+            && (region.allele.starts_with("IG") || region.allele.starts_with("TR"))
         {
             self.genes.push(IMGTGene {
                 acc: region.acc,
                 key: region.key,
                 location: region.location,
                 allele: region.allele,
+                functionality: region.functionality,
+                partial: region.partial,
+                truncation: region.truncation,
                 regions: HashMap::new(),
             });
         } else if [
@@ -513,15 +1141,19 @@ impl DataItem {
             "CDR1-IMGT",
             "CDR2-IMGT",
             "CDR3-IMGT",
+            "L-PART1",
+            "L-PART2",
+            "L-REGION",
             "1st-CYS",
             "2nd-CYS",
             "CONSERVED-TRP",
             "J-REGION",
-            // "J-TRP",
-            // "J-PHE",
-            //"J-MOTIF",
+            "J-TRP",
+            "J-PHE",
+            "J-MOTIF",
             "CH1",
             "CH2",
+            "CH2-CHS",
             "H-CH2",
             "CH3",
             "CH3-CHS",
@@ -540,7 +1172,8 @@ impl DataItem {
             "CHS",
             "CL",
             "C-REGION",
-            "H", //"D-REGION",
+            "H",
+            "D-REGION",
             "H1",
             "H2",
             "H3",
@@ -563,48 +1196,80 @@ impl DataItem {
         }
     }
 
-    fn get_sequence(&self, slice: &Location, shift: usize) -> Result<(String, AASequence), String> {
-        let (inner_shift, shift) = if shift == 2 { (1, 0) } else { (0, shift) };
+    /// Compare the translation reported by IMGT with the translation of the DNA, and handle any mismatch
+    fn check_translation(&mut self, region: &mut Region) {
+        let found = match &region.found_seq {
+            Some(Ok((_, found))) if !region.reported_seq.is_empty() => {
+                found.0.iter().map(|a| a.char()).collect::<String>()
+            }
+            _ => return,
+        };
+        if found == region.reported_seq {
+            return;
+        }
+        let mismatch = GenerateError::TranslationMismatch(region.reported_seq.clone(), found);
+        match self.translation_check {
+            TranslationCheck::Warn => self.warnings.push((region.key.clone(), mismatch)),
+            TranslationCheck::Error => region.found_seq = Some(Err(mismatch)),
+            TranslationCheck::PreferReported => {
+                if let (Ok(reported), Some(Ok((dna, _)))) = (
+                    region
+                        .reported_seq
+                        .bytes()
+                        .map(AminoAcid::try_from)
+                        .collect::<Result<Vec<_>, _>>(),
+                    &region.found_seq,
+                ) {
+                    region.found_seq = Some(Ok((dna.clone(), AASequence(reported))));
+                }
+                self.warnings.push((region.key.clone(), mismatch));
+            }
+        }
+    }
 
+    fn get_sequence(
+        &self,
+        slice: &Location,
+        shift: usize,
+    ) -> Result<(String, AASequence), GenerateError> {
+        let (inner_shift, shift) = if shift == 2 { (1, 0) } else { (0, shift) };
+        let dna = self.get_dna(slice, inner_shift)?;
         translate(
-            &match slice {
-                Location::Normal(range) => {
-                    if *range.start() < inner_shift {
-                        return Err("Shift outside of range".to_string());
-                    }
-                    self.sequence
-                        .get(range.start() - inner_shift..=*range.end())
-                        .ok_or("Normal outside of range")?
-                        .to_string()
-                }
-                Location::SingleNormal(index) => char::from(
-                    *self
-                        .sequence
-                        .as_bytes()
-                        .get(*index)
-                        .ok_or("Single normal outside of range")?,
-                )
-                .to_string(),
-                Location::Complement(range) => complement(
-                    self.sequence
-                        .get(*range.start()..=*range.end() + inner_shift)
-                        .ok_or("Complement outside of range")?
-                        .to_string(),
-                ),
-                Location::SingleComplement(index) => complement(
-                    char::from(
-                        *self
-                            .sequence
-                            .as_bytes()
-                            .get(*index)
-                            .ok_or("Single complement outside of range")?,
-                    )
-                    .to_string(),
-                ),
-            }[shift..],
+            dna.get(shift..)
+                .ok_or(GenerateError::OutOfRange("Shift outside of range"))?,
         )
         .map(|(s, v)| (s.to_owned(), AASequence(v)))
     }
+
+    /// Get the DNA of the given location in reading direction, extended with `inner_shift` nucleotides before the start
+    fn get_dna(&self, slice: &Location, inner_shift: usize) -> Result<String, GenerateError> {
+        let (mut ranges, complemented) = slice.ranges();
+
+        // Extend the first range in reading direction with the inner shift
+        if complemented {
+            if let Some(last) = ranges.last_mut() {
+                *last = *last.start()..=*last.end() + inner_shift;
+            }
+        } else if let Some(first) = ranges.first_mut() {
+            if *first.start() < inner_shift {
+                return Err(GenerateError::OutOfRange("Shift outside of range"));
+            }
+            *first = first.start() - inner_shift..=*first.end();
+        }
+
+        let mut dna = String::new();
+        for range in ranges {
+            dna.push_str(
+                self.sequence
+                    .get(range)
+                    .ok_or(GenerateError::OutOfRange("Location outside of range"))?,
+            );
+        }
+        if complemented {
+            dna = complement(dna);
+        }
+        Ok(dna)
+    }
 }
 
 #[derive(Clone, Hash, Eq, PartialEq)]
@@ -641,31 +1306,34 @@ fn complement(s: String) -> String {
     .unwrap()
 }
 
-fn translate(s: &str) -> Result<(&str, Vec<AminoAcid>), String> {
-    if s.len() < 3 {
-        Ok((s, Vec::new()))
-    } else {
-        Ok((
-            s,
-            (0..=s.len() - 3)
-                .step_by(3)
-                .filter_map(|chunk| {
-                    invert(
-                        AminoAcid::from_dna(&s[chunk..chunk + 3])
-                            .map_err(|_| format!("Not a codon {}", &s[chunk..chunk + 3])),
-                    )
-                })
-                .collect::<Result<Vec<AminoAcid>, String>>()?,
-        ))
-    }
+/// Translate the DNA in the first reading frame. A stop codon is translated as `X` to keep the positions of the
+/// following amino acids, except for a stop codon at the very end which is not part of the protein.
+fn translate(s: &str) -> Result<(&str, Vec<AminoAcid>), GenerateError> {
+    let codons = s.len() / 3;
+    Ok((
+        s,
+        (0..codons)
+            .filter_map(|index| {
+                let codon = &s[index * 3..index * 3 + 3];
+                match AminoAcid::from_dna(codon) {
+                    Ok(Some(aa)) => Some(Ok(aa)),
+                    Ok(None) if index + 1 == codons => None,
+                    Ok(None) => Some(Ok(AminoAcid::X)),
+                    Err(_) => Some(Err(GenerateError::Translation(codon.to_string()))),
+                }
+            })
+            .collect::<Result<Vec<AminoAcid>, GenerateError>>()?,
+    ))
 }
 
-fn invert<T, E>(x: Result<Option<T>, E>) -> Option<Result<T, E>> {
-    match x {
-        Ok(None) => None,
-        Ok(Some(a)) => Some(Ok(a)),
-        Err(e) => Some(Err(e)),
-    }
+/// Translate a D gene in the given reading frame. A frame with a stop codon cannot be part of a productive
+/// rearrangement, so it gives `None`.
+fn translate_d_frame(
+    dna: &str,
+    frame: usize,
+) -> Result<Option<(&str, Vec<AminoAcid>)>, GenerateError> {
+    let (dna, aa) = translate(dna.get(frame..).unwrap_or_default())?;
+    Ok((aa.len() == dna.len() / 3 && !aa.contains(&AminoAcid::X)).then_some((dna, aa)))
 }
 
 impl Display for DataItem {
@@ -701,52 +1369,114 @@ impl Display for Region {
             // self.sequence,
             // dna,
             // self.found_seq.0,
-            self.found_seq
-                .as_ref()
-                .map(|seq| seq.1 .0.iter().map(|a| a.char()).collect::<String>())
-                .unwrap_or_else(|e| format!("<NO SEQ!>: {e}")),
+            self.found_seq.as_ref().map_or_else(
+                || "<NOT LOADED>".to_string(),
+                |seq| seq
+                    .as_ref()
+                    .map(|seq| seq.1 .0.iter().map(|a| a.char()).collect::<String>())
+                    .unwrap_or_else(|e| format!("<NO SEQ!>: {e}"))
+            ),
         )
     }
 }
 
 impl IMGTGene {
-    fn finish(self) -> Result<SingleSeq, String> {
-        let get = |key| -> Result<(Vec<AminoAcid>, Location, String), String> {
+    /// Build the final sequence(s) for this gene, D genes give one sequence per reading frame without a stop codon
+    fn finish(mut self) -> Result<Vec<SingleSeq>, GenerateError> {
+        let get = |key: &str| -> Result<Section, GenerateError> {
             self.regions
                 .get(key)
-                .ok_or(format!("Could not find {key}"))
-                .and_then(|region| {
-                    region
-                        .found_seq
-                        .as_ref()
-                        .map(|seq| {
-                            let mut final_seq = region
-                                .splice_aa
-                                .map(|aa| vec![aa])
-                                .filter(|_| region.shift != 2)
-                                .unwrap_or_default();
-                            final_seq.extend(seq.1 .0.clone());
-                            (final_seq, region.location.clone(), seq.0.clone())
+                .ok_or(GenerateError::MissingRegion(key.to_string()))
+                .and_then(Region::section)
+        };
+        let (name, allele) = Gene::from_imgt_name_with_allele(self.allele.as_str())
+            .map_err(GenerateError::NameParse)?;
+        if self.key == "D-GENE" {
+            let (_, location, dna) = get("D-REGION")?;
+            return (0..3)
+                .filter_map(|frame| {
+                    translate_d_frame(&dna, frame)
+                        .transpose()
+                        .map(|translated| {
+                            let (dna, aa) = translated?;
+                            self.single_seq(
+                                name.clone(),
+                                allele,
+                                vec![(
+                                    shared::Region::CDR3,
+                                    (aa, location.clone(), dna.to_string()),
+                                )],
+                                Vec::new(),
+                                Some(frame),
+                                None,
+                            )
                         })
-                        .map_err(|e| e.to_owned())
                 })
+                .collect();
+        }
+        // The leader peptide is stored separately from the mature V sequence, it is taken from the
+        // spliced L-REGION if annotated, otherwise from the L-PART1 and L-PART2 exons
+        let leader = if self.key == "V-GENE" {
+            get("L-REGION")
+                .map(|region| region.0)
+                .or_else(|_| {
+                    let mut leader = get("L-PART1")?.0;
+                    leader.extend(get("L-PART2")?.0);
+                    Ok::<_, GenerateError>(leader)
+                })
+                .ok()
+                .filter(|leader| !leader.is_empty())
+        } else {
+            None
         };
         let mut additional_annotations = Vec::new();
+        let mut membrane_regions = None;
         let regions = if self.key == "V-GENE" {
-            vec![
-                (shared::Region::FR1, get("FR1-IMGT")?),
-                (shared::Region::CDR1, get("CDR1-IMGT")?),
-                (shared::Region::FR2, get("FR2-IMGT")?),
-                (shared::Region::CDR2, get("CDR2-IMGT")?),
-                (shared::Region::FR3, get("FR3-IMGT")?),
-                (shared::Region::CDR3, get("CDR3-IMGT")?),
-            ]
+            let keys = [
+                (shared::Region::FR1, "FR1-IMGT"),
+                (shared::Region::CDR1, "CDR1-IMGT"),
+                (shared::Region::FR2, "FR2-IMGT"),
+                (shared::Region::CDR2, "CDR2-IMGT"),
+                (shared::Region::FR3, "FR3-IMGT"),
+                (shared::Region::CDR3, "CDR3-IMGT"),
+            ];
+            if self.partial {
+                // Partial genes can miss regions at either end, but not in the middle
+                let mut regions = Vec::new();
+                let mut gap = false;
+                for (region, key) in keys {
+                    if self.regions.contains_key(key) {
+                        if gap {
+                            return Err(GenerateError::InvalidPartial(format!(
+                                "Partial gene misses regions before {key}"
+                            )));
+                        }
+                        regions.push((region, get(key)?));
+                    } else if regions.is_empty() {
+                        self.truncation.five_prime = true;
+                    } else {
+                        gap = true;
+                    }
+                }
+                self.truncation.three_prime |= gap;
+                if regions.is_empty() {
+                    return Err(GenerateError::InvalidPartial(
+                        "Partial gene without any regions".to_string(),
+                    ));
+                }
+                regions
+            } else {
+                keys.into_iter()
+                    .map(|(region, key)| Ok((region, get(key)?)))
+                    .collect::<Result<Vec<_>, GenerateError>>()?
+            }
         } else if self.key == "C-GENE" {
-            // if self.allele == "IGHA1*01" {
-            //     dbg!(&self);
-            // }
             let mut seq = Vec::new();
-            let mut possibly_add = |region, key: &str, only_if_empty: bool| -> Result<(), String> {
+            let possibly_add = |seq: &mut Vec<_>,
+                                region,
+                                key: &str,
+                                only_if_empty: bool|
+             -> Result<(), GenerateError> {
                 if self.regions.contains_key(key)
                     && ((only_if_empty && seq.is_empty()) || !only_if_empty)
                 {
@@ -754,179 +1484,297 @@ impl IMGTGene {
                         region,
                         self.regions
                             .get(key)
-                            .ok_or(format!("Could not find {key}"))
-                            .and_then(|region| {
-                                region
-                                    .found_seq
-                                    .as_ref()
-                                    .map(|seq| {
-                                        let mut final_seq = region
-                                            .splice_aa
-                                            .map(|aa| vec![aa])
-                                            .filter(|_| region.shift != 2)
-                                            .unwrap_or_default();
-                                        final_seq.extend(seq.1 .0.clone());
-                                        (final_seq, region.location.clone(), seq.0.clone())
-                                    })
-                                    .map_err(|e| e.to_owned())
-                            })?,
+                            .ok_or(GenerateError::MissingRegion(key.to_string()))
+                            .and_then(Region::section)?,
                     ))
                 }
                 Ok(())
             };
 
             // Heavy chain
-            possibly_add(shared::Region::CH1, "CH1", false)?;
+            possibly_add(&mut seq, shared::Region::CH1, "CH1", false)?;
+            // The CH2 including the secretory tail, for genes that end after the CH2
+            let ch2_secretory = self.regions.contains_key("CH2-CHS")
+                && !(self.regions.contains_key("CH2") && self.regions.contains_key("CHS"));
+            let add_ch2 = |seq: &mut Vec<_>| {
+                if ch2_secretory {
+                    possibly_add(seq, shared::Region::CH2_CHS, "CH2-CHS", false)
+                } else {
+                    possibly_add(seq, shared::Region::CH2, "CH2", false)
+                }
+            };
             // Try to detect the best H/CH2
-            if self.regions.contains_key("H") && self.regions.contains_key("CH2") {
-                possibly_add(shared::Region::H, "H", false)?;
-                possibly_add(shared::Region::CH2, "CH2", false)?;
+            let mut hinge = Vec::new();
+            if self.regions.contains_key("H") && (self.regions.contains_key("CH2") || ch2_secretory)
+            {
+                possibly_add(&mut hinge, shared::Region::Hinge(1, None), "H", false)?;
+                seq.extend(split_hinge(hinge));
+                add_ch2(&mut seq)?;
             } else if self.regions.contains_key("H-CH2") {
-                possibly_add(shared::Region::H_CH2, "H-CH2", false)?;
+                possibly_add(&mut seq, shared::Region::H_CH2, "H-CH2", false)?;
             } else {
-                possibly_add(shared::Region::H, "H1", false)?;
-                possibly_add(shared::Region::H, "H2", false)?;
-                possibly_add(shared::Region::H, "H3", false)?;
-                possibly_add(shared::Region::H, "H4", false)?;
-                possibly_add(shared::Region::CH2, "CH2", false)?;
+                for (exon, key) in [(1, "H1"), (2, "H2"), (3, "H3"), (4, "H4")] {
+                    possibly_add(&mut hinge, shared::Region::Hinge(exon, None), key, false)?;
+                }
+                seq.extend(split_hinge(hinge));
+                add_ch2(&mut seq)?;
             }
-            let mut secretory = false;
+            let mut secretory = ch2_secretory;
             if self.regions.contains_key("CH3") && self.regions.contains_key("CHS") {
-                possibly_add(shared::Region::CH3, "CH3", false)?;
+                possibly_add(&mut seq, shared::Region::CH3, "CH3", false)?;
             } else if self.regions.contains_key("CH3-CHS") {
-                possibly_add(shared::Region::CH3_CHS, "CH3-CHS", false)?;
+                possibly_add(&mut seq, shared::Region::CH3_CHS, "CH3-CHS", false)?;
                 secretory = true;
             }
             if self.regions.contains_key("CH4") && self.regions.contains_key("CHS") {
-                possibly_add(shared::Region::CH4, "CH4", false)?;
+                possibly_add(&mut seq, shared::Region::CH4, "CH4", false)?;
             } else if self.regions.contains_key("CH4-CHS") {
-                possibly_add(shared::Region::CH4_CHS, "CH4-CHS", false)?;
+                possibly_add(&mut seq, shared::Region::CH4_CHS, "CH4-CHS", false)?;
                 secretory = true;
             }
             if self.regions.contains_key("CH5") && self.regions.contains_key("CHS") {
-                possibly_add(shared::Region::CH5, "CH5", false)?;
+                possibly_add(&mut seq, shared::Region::CH5, "CH5", false)?;
             } else if self.regions.contains_key("CH5-CHS") {
-                possibly_add(shared::Region::CH5_CHS, "CH5-CHS", false)?;
+                possibly_add(&mut seq, shared::Region::CH5_CHS, "CH5-CHS", false)?;
                 secretory = true;
             }
             if self.regions.contains_key("CH6") && self.regions.contains_key("CHS") {
-                possibly_add(shared::Region::CH6, "CH6", false)?;
+                possibly_add(&mut seq, shared::Region::CH6, "CH6", false)?;
             } else if self.regions.contains_key("CH6-CHS") {
-                possibly_add(shared::Region::CH6_CHS, "CH6-CHS", false)?;
+                possibly_add(&mut seq, shared::Region::CH6_CHS, "CH6-CHS", false)?;
                 secretory = true;
             }
             if self.regions.contains_key("CH7") && self.regions.contains_key("CHS") {
-                possibly_add(shared::Region::CH7, "CH7", false)?;
+                possibly_add(&mut seq, shared::Region::CH7, "CH7", false)?;
             } else if self.regions.contains_key("CH7-CHS") {
-                possibly_add(shared::Region::CH7_CHS, "CH7-CHS", false)?;
+                possibly_add(&mut seq, shared::Region::CH7_CHS, "CH7-CHS", false)?;
                 secretory = true;
             }
             if self.regions.contains_key("CH8") && self.regions.contains_key("CHS") {
-                possibly_add(shared::Region::CH8, "CH8", false)?;
+                possibly_add(&mut seq, shared::Region::CH8, "CH8", false)?;
             } else if self.regions.contains_key("CH8-CHS") {
-                possibly_add(shared::Region::CH8_CHS, "CH8-CHS", false)?;
+                possibly_add(&mut seq, shared::Region::CH8_CHS, "CH8-CHS", false)?;
                 secretory = true;
             }
             if self.regions.contains_key("CH9") && self.regions.contains_key("CHS") {
-                possibly_add(shared::Region::CH9, "CH9", false)?;
+                possibly_add(&mut seq, shared::Region::CH9, "CH9", false)?;
             } else if self.regions.contains_key("CH9-CHS") {
-                possibly_add(shared::Region::CH9_CHS, "CH9-CHS", false)?;
+                possibly_add(&mut seq, shared::Region::CH9_CHS, "CH9-CHS", false)?;
                 secretory = true;
             }
             if !secretory {
-                possibly_add(shared::Region::CHS, "CHS", false)?;
+                possibly_add(&mut seq, shared::Region::CHS, "CHS", false)?;
+            }
+
+            // The membrane bound form splices the last domain without its secretory tail to the membrane exons
+            if ["M", "M1", "M2"]
+                .iter()
+                .any(|key| self.regions.contains_key(*key))
+            {
+                let mut membrane = Vec::new();
+                let mut complete = true;
+                for (region, data) in &seq {
+                    if *region == shared::Region::CHS {
+                        continue;
+                    }
+                    if let Some((domain, key)) = membrane_domain(*region) {
+                        complete &= self.regions.contains_key(key);
+                        possibly_add(&mut membrane, domain, key, false)?;
+                    } else {
+                        membrane.push((*region, data.clone()));
+                    }
+                }
+                possibly_add(&mut membrane, shared::Region::M, "M", false)?;
+                possibly_add(&mut membrane, shared::Region::M1, "M1", false)?;
+                possibly_add(&mut membrane, shared::Region::M2, "M2", false)?;
+                if complete {
+                    membrane_regions = Some(membrane);
+                }
             }
-            // possibly_add(shared::Region::M, "M")?; // TODO: Figure out if support for membrane bound is needed, if so provide a way to switch between the two versions
-            // possibly_add(shared::Region::M1, "M1")?;
-            // possibly_add(shared::Region::M2, "M2")?;
 
             // Otherwise assume light chain
-            possibly_add(shared::Region::CL, "CL", true)?;
-            possibly_add(shared::Region::CL, "C-REGION", true)?;
+            possibly_add(&mut seq, shared::Region::CL, "CL", true)?;
+            possibly_add(&mut seq, shared::Region::CL, "C-REGION", true)?;
 
             if seq.is_empty() {
-                return Err("Empty C sequence".to_string());
+                return Err(GenerateError::EmptyConstant);
             }
             seq
         } else if self.key == "J-GENE" {
-            // if self.regions.contains_key("FR4-IMGT") {
-            //     let fr4 = get("FR4-IMGT")?;
-            //     let j = get("J-REGION")?;
-            //     let cdr3_len = j.0.len() - fr4.0.len();
-            //     let j = fix_j(j, cdr3_len);
-            //     additional_annotations.extend(j.1);
-            //     j.0
-            // } else if self.regions.contains_key("J-MOTIF") {
-            //     let motif = get("J-MOTIF")?;
-            //     let j = get("J-REGION")?;
-            //     let loc =
-            //         j.1.get_aa_loc(&motif.1)
-            //             .ok_or("J-MOTIF does not fall into J-REGION")?;
-            //     let j = fix_j(j, *loc.start());
-            //     additional_annotations.extend(j.1);
-            //     j.0
-            // } else {
-            // if self.allele == "IGKJ1*01" {
-            //     dbg!(&self);
-            // }
             let j = get("J-REGION")?;
-            let motif = j.0.iter().tuple_windows().position(|(a, b, _, d)| {
-                (*a == AminoAcid::W || *a == AminoAcid::F)
-                    && *b == AminoAcid::G
-                    && *d == AminoAcid::G
-            });
-            if let Some(motif_start) = motif {
-                let j = fix_j(j, motif_start);
+            let j_region = &self.regions["J-REGION"];
+            // Use the boundary as annotated by IMGT, otherwise find the W/F-G-x-G motif. J genes without
+            // either are split later on, based on the consensus motif of the other J genes (see `split_j_genes`).
+            let boundary = ["FR4-IMGT", "J-TRP", "J-PHE", "J-MOTIF"]
+                .iter()
+                .find_map(|key| {
+                    self.regions
+                        .get(*key)
+                        .and_then(|region| j_region.aa_index(&region.location))
+                })
+                .or_else(|| {
+                    j.0.iter().tuple_windows().position(|(a, b, _, d)| {
+                        (*a == AminoAcid::W || *a == AminoAcid::F)
+                            && *b == AminoAcid::G
+                            && *d == AminoAcid::G
+                    })
+                })
+                .filter(|boundary| *boundary < j.0.len());
+            if let Some(boundary) = boundary {
+                let j = fix_j(j, boundary);
                 additional_annotations.extend(j.1);
                 j.0
             } else {
-                vec![(shared::Region::FR4, j)] // TODO: not fully correct right, has some CDR3 as well, and has quite some conserved residues
+                vec![(shared::Region::FR4, j)]
             }
-            // }
-        } else if self.key == "D-GENE" {
-            vec![(shared::Region::CDR3, get("D-REGION")?)]
         } else {
             Vec::new()
         };
+        if self.partial && !self.truncation.is_partial() {
+            return Err(GenerateError::InvalidPartial(
+                "Partial gene without a known truncated end".to_string(),
+            ));
+        }
+        let isoform = (self.key == "C-GENE").then_some(Isoform::Secreted);
+        let mut first = self.single_seq(
+            name.clone(),
+            allele,
+            regions,
+            additional_annotations,
+            None,
+            isoform,
+        )?;
+        first.sequence.leader = leader.map(Into::into);
+        let mut output = vec![first];
+        if let Some(membrane) = membrane_regions {
+            output.push(self.single_seq(
+                name,
+                allele,
+                membrane,
+                Vec::new(),
+                None,
+                Some(Isoform::Membrane),
+            )?);
+        }
+        Ok(output)
+    }
+
+    /// Get the length of the DNA of every region, where the nucleotides of a codon spanning a splice junction
+    /// are counted for the region that contains the amino acid of that codon
+    fn codon_aligned_lengths(
+        &self,
+        regions: &[(shared::Region, Section)],
+    ) -> Vec<(shared::Region, usize)> {
+        let mut lengths = regions
+            .iter()
+            .map(|reg| (reg.0, reg.1 .2.len()))
+            .collect_vec();
+        for index in 1..regions.len() {
+            // With codon_start=2 the first two nucleotides of the codon are at the end of the previous exon
+            let spliced = regions[index].1 .1.first().is_some_and(|first| {
+                self.regions.values().any(|region| {
+                    region.splice_aa.is_some()
+                        && region.shift == 1
+                        && region.location.spliced_offset(first) == Some(0)
+                })
+            });
+            if spliced && lengths[index - 1].1 % 3 == 2 {
+                lengths[index - 1].1 -= 2;
+                lengths[index].1 += 2;
+            }
+        }
+        lengths
+    }
+
+    fn single_seq(
+        &self,
+        name: Gene,
+        allele: usize,
+        regions: Vec<(shared::Region, Section)>,
+        additional_annotations: Vec<(Annotation, usize)>,
+        reading_frame: Option<usize>,
+        isoform: Option<Isoform>,
+    ) -> Result<SingleSeq, GenerateError> {
         let sequence: Vec<AminoAcid> = regions.iter().flat_map(|reg| reg.1 .0.clone()).collect();
         let dna: String = regions.iter().map(|reg| reg.1 .2.clone()).collect();
+        let dna_region_lengths = self.codon_aligned_lengths(&regions);
         let region_lengths = regions.iter().map(|reg| (reg.0, reg.1 .0.len())).collect();
+        // The J motif (J-TRP/J-PHE) is annotated based on the CDR3/FR4 boundary, see `fix_j`
         let conserved_map = HashMap::from([
             ("1st-CYS", Annotation::Cysteine1),
             ("2nd-CYS", Annotation::Cysteine2),
             ("CONSERVED-TRP", Annotation::Tryptophan),
-            ("J-PHE", Annotation::Phenylalanine),
-            ("J-TRP", Annotation::Tryptophan),
         ]);
-        let mut conserved = self
+        let conserved = self
             .regions
             .iter()
-            .filter(|(key, _)| {
-                ["1st-CYS", "2nd-CYS", "CONSERVED-TRP", "J-PHE", "J-TRP"].contains(&key.as_str())
-            })
+            .filter(|(key, _)| conserved_map.contains_key(key.as_str()))
             .map(|(key, region)| {
                 find_aa_location(&region.location, &regions)
                     .map(|index| (conserved_map[key.as_str()], index))
-                    .ok_or(format!("Cannot find location of '{key}' '{region}'"))
+                    .ok_or_else(|| GenerateError::MissingAnnotation(format!("'{key}' '{region}'")))
             })
-            .collect::<Result<Vec<_>, _>>()?;
-        conserved.extend(
-            find_possible_n_glycan_locations(&sequence)
-                .iter()
-                .map(|i| (Annotation::NGlycan, *i)),
-        );
-        conserved.extend(additional_annotations);
-        let (name, allele) = Gene::from_imgt_name_with_allele(self.allele.as_str())?;
+            .collect::<Result<Vec<_>, _>>()?
+            .into_iter()
+            .chain(additional_annotations)
+            .collect();
         Ok(SingleSeq {
             name,
             allele,
             acc: self.acc.clone(),
-            sequence: AnnotatedSequence::new(sequence.into(), region_lengths, conserved),
-            dna,
+            sequence: AnnotatedSequence {
+                reading_frame,
+                isoform,
+                functionality: self
+                    .functionality
+                    .ok_or(GenerateError::MissingFunctionality)?,
+                dna,
+                dna_regions: dna_region_lengths,
+                truncation: self.truncation,
+                ..AnnotatedSequence::new(sequence.into(), region_lengths, conserved)
+            },
         })
     }
 }
 
+/// Get the domain without the secretory tail (and its IMGT key) for a domain that includes the secretory tail
+const fn membrane_domain(region: shared::Region) -> Option<(shared::Region, &'static str)> {
+    match region {
+        shared::Region::CH2_CHS => Some((shared::Region::CH2, "CH2")),
+        shared::Region::CH3_CHS => Some((shared::Region::CH3, "CH3")),
+        shared::Region::CH4_CHS => Some((shared::Region::CH4, "CH4")),
+        shared::Region::CH5_CHS => Some((shared::Region::CH5, "CH5")),
+        shared::Region::CH6_CHS => Some((shared::Region::CH6, "CH6")),
+        shared::Region::CH7_CHS => Some((shared::Region::CH7, "CH7")),
+        shared::Region::CH8_CHS => Some((shared::Region::CH8, "CH8")),
+        shared::Region::CH9_CHS => Some((shared::Region::CH9, "CH9")),
+        _ => None,
+    }
+}
+
+/// Check the consistency of a final sequence, every violation is added to `invalid`
+fn is_valid(
+    species: Species,
+    name: &str,
+    sequence: &AnnotatedSequence,
+    invalid: &mut Vec<(Species, String, GenerateError)>,
+) -> bool {
+    let violations = shared::validate(
+        &sequence.sequence,
+        &sequence.regions,
+        &sequence.annotations,
+        &sequence.dna,
+        &sequence.dna_regions,
+    );
+    let valid = violations.is_empty();
+    invalid.extend(
+        violations
+            .into_iter()
+            .map(|violation| (species, name.to_string(), GenerateError::Invalid(violation))),
+    );
+    valid
+}
+
 fn find_aa_location(
     location: &Location,
     sections: &[(shared::Region, (Vec<AminoAcid>, Location, String))],
@@ -941,49 +1789,359 @@ fn find_aa_location(
     None
 }
 
-fn find_possible_n_glycan_locations(sequence: &[AminoAcid]) -> Vec<usize> {
+/// Find the possible glycosylation sites of a sequence. N-x-S/T sequons where x is not a proline are
+/// possible N linked glycans, if `atypical` is set N-x-C sequons are annotated separately as atypical sites.
+/// The Q-Y/F-N-S-T sequon in the CH2 of IgG (Asn297) is known to be occupied. Serines and threonines in the hinge with a proline within three
+/// residues are candidate O linked glycans, for combined hinge and CH2 exons (eg IgA) the hinge is
+/// taken to run up to the first cysteine.
+fn find_glycan_locations(
+    sequence: &[AminoAcid],
+    regions: &[(shared::Region, usize)],
+    atypical: bool,
+) -> Vec<(Annotation, usize)> {
     let mut result = Vec::new();
+    let region_at = regions
+        .iter()
+        .flat_map(|(region, length)| std::iter::repeat(*region).take(*length))
+        .collect_vec();
     for (index, aa) in sequence.windows(3).enumerate() {
-        if aa[0] == AminoAcid::N && (aa[2] == AminoAcid::S || aa[2] == AminoAcid::T) {
-            result.push(index);
+        if aa[0] != AminoAcid::N || aa[1] == AminoAcid::P {
+            continue;
+        }
+        if aa[2] == AminoAcid::S || aa[2] == AminoAcid::T {
+            let occupied = matches!(
+                region_at.get(index),
+                Some(shared::Region::CH2 | shared::Region::CH2_CHS)
+            ) && index >= 2
+                && sequence[index - 2] == AminoAcid::Q
+                && (sequence[index - 1] == AminoAcid::Y || sequence[index - 1] == AminoAcid::F)
+                && aa[1] == AminoAcid::S
+                && aa[2] == AminoAcid::T;
+            result.push((
+                if occupied {
+                    Annotation::NGlycanOccupied
+                } else {
+                    Annotation::NGlycan
+                },
+                index,
+            ));
+        } else if atypical && aa[2] == AminoAcid::C {
+            result.push((Annotation::NGlycanAtypical, index));
+        }
+    }
+    let mut start = 0;
+    for (region, length) in regions {
+        let section = sequence
+            .get(start..(start + length).min(sequence.len()))
+            .unwrap_or_default();
+        let hinge_length = match region {
+            shared::Region::Hinge(..) => section.len(),
+            shared::Region::H_CH2 => section
+                .iter()
+                .position(|aa| *aa == AminoAcid::C)
+                .unwrap_or_default(),
+            _ => 0,
+        };
+        for index in start..start + hinge_length {
+            let near = &sequence[index.saturating_sub(3)..(index + 4).min(sequence.len())];
+            if (sequence[index] == AminoAcid::S || sequence[index] == AminoAcid::T)
+                && near.contains(&AminoAcid::P)
+            {
+                result.push((Annotation::OGlycan, index));
+            }
         }
+        start += length;
     }
     result
 }
 
-fn fix_j(
-    j: (Vec<AminoAcid>, Location, String),
-    cdr3_length: usize,
-) -> (
-    Vec<(shared::Region, (Vec<AminoAcid>, Location, String))>,
-    Vec<(Annotation, usize)>,
-) {
-    let (cdr3_loc, fr4_loc) =
-        j.1.splice(cdr3_length)
-            .expect("CDR3 should fit in full FR4 of J gene");
-    let cdr3 = (
-        j.0[..cdr3_length].to_vec(),
-        cdr3_loc,
-        j.2[..cdr3_length].to_owned(),
-    );
-    let fr4 = (
-        j.0[cdr3_length..].to_vec(),
-        fr4_loc,
-        j.2[cdr3_length..].to_owned(),
+/// Find the disulfide bonds of a sequence:
+/// * The conserved cysteines of the V domain (Cys23 and Cys104) are bonded
+/// * Within every constant domain cysteines 50 to 80 residues apart are bonded, preferring a spacing close to
+///   60 residues
+/// * The last unpaired cysteine of the CL bonds the heavy chain
+/// * The first unpaired cysteine of the CH1 bonds the light chain (eg IgG2, IgG4, IgM), if there is none the
+///   first cysteine of the upper hinge bonds the light chain instead (eg IgG1)
+/// * All other cysteines in the hinge bond the other heavy chain
+///
+/// The inter chain bonds are only annotated for immunoglobulins, the constant regions of T cell receptors
+/// are labelled CL as well but only get their intra chain bonds. The inter heavy chain bonds outside of
+/// the hinge (eg for IgM and IgA multimers) are not annotated.
+fn find_disulfides(
+    sequence: &[AminoAcid],
+    regions: &[(shared::Region, usize)],
+    annotations: &[(Annotation, usize)],
+    chain: ChainType,
+) -> Vec<(Annotation, usize)> {
+    let mut result = Vec::new();
+    let conserved = |kind| annotations.iter().find(|a| a.0 == kind).map(|a| a.1);
+    if let (Some(first), Some(second)) = (
+        conserved(Annotation::Cysteine1),
+        conserved(Annotation::Cysteine2),
+    ) {
+        result.push((
+            Annotation::Disulfide(DisulfidePartner::Intra(second)),
+            first,
+        ));
+        result.push((
+            Annotation::Disulfide(DisulfidePartner::Intra(first)),
+            second,
+        ));
+    }
+
+    let mut sections = Vec::new();
+    let mut start = 0;
+    for (region, length) in regions {
+        let end = (start + length).min(sequence.len());
+        let cysteines = (start..end)
+            .filter(|index| sequence[*index] == AminoAcid::C)
+            .collect_vec();
+        sections.push((*region, cysteines));
+        start += length;
+    }
+    let mut paired = Vec::new();
+    for (region, cysteines) in &sections {
+        if !is_constant_domain(*region) {
+            continue;
+        }
+        // The pairs closest to the typical spacing are taken first, so an unpaired cysteine in front of
+        // the domain bond (eg the light chain bonding cysteine in the CH1 of IgG2) is not paired instead
+        let pairs = cysteines
+            .iter()
+            .copied()
+            .tuple_combinations()
+            .filter(|(first, second)| (50..=80).contains(&(second - first)))
+            .sorted_by_key(|(first, second)| ((second - first).abs_diff(60), *first))
+            .fold(
+                Vec::new(),
+                |mut pairs: Vec<(usize, usize)>, (first, second)| {
+                    if !pairs
+                        .iter()
+                        .any(|&(a, b)| [a, b].contains(&first) || [a, b].contains(&second))
+                    {
+                        pairs.push((first, second));
+                    }
+                    pairs
+                },
+            );
+        for (first, second) in pairs.into_iter().sorted() {
+            result.push((
+                Annotation::Disulfide(DisulfidePartner::Intra(second)),
+                first,
+            ));
+            result.push((
+                Annotation::Disulfide(DisulfidePartner::Intra(first)),
+                second,
+            ));
+            paired.extend([first, second]);
+        }
+    }
+    if chain.is_t_cell_receptor() {
+        return result;
+    }
+
+    let cysteines = |filter: fn(&shared::Region) -> bool| {
+        sections
+            .iter()
+            .filter(move |(region, _)| filter(region))
+            .flat_map(|(_, cysteines)| cysteines.iter().copied())
+            .filter(|index| !paired.contains(index))
+    };
+    if let Some(index) = cysteines(|r| *r == shared::Region::CL).next_back() {
+        result.push((Annotation::Disulfide(DisulfidePartner::HeavyChain), index));
+    }
+    let light = cysteines(|r| *r == shared::Region::CH1).next().or_else(|| {
+        cysteines(|r| matches!(r, shared::Region::Hinge(_, Some(HingePart::Upper)))).next()
+    });
+    if let Some(index) = light {
+        result.push((Annotation::Disulfide(DisulfidePartner::LightChain), index));
+    }
+    result.extend(
+        cysteines(|r| matches!(r, shared::Region::Hinge(..)))
+            .filter(|index| Some(*index) != light)
+            .map(|index| (Annotation::Disulfide(DisulfidePartner::HeavyChain), index)),
     );
+    result
+}
 
-    let mut annotations = Vec::new();
-    if fr4.0[0] == AminoAcid::W {
-        annotations.push((Annotation::Tryptophan, cdr3_length));
-    } else if fr4.0[0] == AminoAcid::F {
-        annotations.push((Annotation::Phenylalanine, cdr3_length));
+/// If this region is a single constant domain (possibly with the secretory tail)
+const fn is_constant_domain(region: shared::Region) -> bool {
+    matches!(
+        region,
+        shared::Region::CH1
+            | shared::Region::CH2
+            | shared::Region::CH3
+            | shared::Region::CH4
+            | shared::Region::CH5
+            | shared::Region::CH6
+            | shared::Region::CH7
+            | shared::Region::CH8
+            | shared::Region::CH9
+            | shared::Region::CH2_CHS
+            | shared::Region::CH3_CHS
+            | shared::Region::CH4_CHS
+            | shared::Region::CH5_CHS
+            | shared::Region::CH6_CHS
+            | shared::Region::CH7_CHS
+            | shared::Region::CH8_CHS
+            | shared::Region::CH9_CHS
+            | shared::Region::CL
+    )
+}
+
+/// Annotate the disulfide bonds of all sequences, see [`find_disulfides`]
+fn annotate_disulfides(sequences: &mut [(Species, SingleSeq)]) {
+    for (_, single) in sequences.iter_mut() {
+        let aa = single
+            .sequence
+            .sequence
+            .sequence
+            .iter()
+            .map(|element| element.aminoacid)
+            .collect_vec();
+        let disulfides = find_disulfides(
+            &aa,
+            &single.sequence.regions,
+            &single.sequence.annotations,
+            single.name.chain,
+        );
+        if !disulfides.is_empty() {
+            single.sequence.annotations.extend(disulfides);
+            single.sequence.annotations.sort_unstable_by_key(|a| a.1);
+        }
     }
-    if fr4.0[1] == AminoAcid::G {
-        annotations.push((Annotation::Glycine, cdr3_length + 1));
+}
+
+/// Annotate the possible glycosylation sites of all sequences, see [`find_glycan_locations`]
+fn annotate_glycans(sequences: &mut [(Species, SingleSeq)], atypical: bool) {
+    for (_, single) in sequences.iter_mut() {
+        let aa = single
+            .sequence
+            .sequence
+            .sequence
+            .iter()
+            .map(|element| element.aminoacid)
+            .collect_vec();
+        let glycans = find_glycan_locations(&aa, &single.sequence.regions, atypical);
+        if !glycans.is_empty() {
+            single.sequence.annotations.extend(glycans);
+            single.sequence.annotations.sort_unstable_by_key(|a| a.1);
+        }
     }
-    if fr4.0[3] == AminoAcid::G {
-        annotations.push((Annotation::Glycine, cdr3_length + 3));
+}
+
+/// Annotate the residues defining the allotypes of human constant genes, see [`Allotype`]
+fn annotate_allotypes(sequences: &mut [(Species, SingleSeq)]) {
+    for (species, single) in sequences.iter_mut() {
+        if !matches!(single.name.gene, GeneType::C(_)) {
+            continue;
+        }
+        let aa = single
+            .sequence
+            .sequence
+            .sequence
+            .iter()
+            .map(|element| element.aminoacid)
+            .collect_vec();
+        let allotypes = Allotype::annotate(*species, &single.name, &aa);
+        if !allotypes.is_empty() {
+            single.sequence.annotations.extend(allotypes);
+            single.sequence.annotations.sort_unstable_by_key(|a| a.1);
+        }
+    }
+}
+
+/// The amino acid sequence of a region with its location and DNA
+type Section = (Vec<AminoAcid>, Location, String);
+
+/// Split a section at the given amino acid index, None if either side would be empty
+fn split_section(section: &Section, index: usize) -> Option<(Section, Section)> {
+    if index == 0 || index >= section.0.len() {
+        return None;
     }
+    let (left, right) = section.1.splice(index)?;
+    let dna_split = (index * 3).min(section.2.len());
+    Some((
+        (
+            section.0[..index].to_vec(),
+            left,
+            section.2[..dna_split].to_owned(),
+        ),
+        (
+            section.0[index..].to_vec(),
+            right,
+            section.2[dna_split..].to_owned(),
+        ),
+    ))
+}
+
+/// Split the hinge exons in the upper, core, and lower hinge. The core hinge runs from the first C-P-x-C
+/// motif up to and including the last one (and a directly following proline), the upper hinge precedes it
+/// and the lower hinge follows it. The exon numbers are kept, if no motif is found or the exons cannot be split
+/// the exons are kept whole.
+///
+/// Only the hinge exons are split, so the lower hinge of `IgG` (eg APELLGG for `IgG1`) that is encoded at the
+/// start of the CH2 exon stays part of CH2.
+fn split_hinge(exons: Vec<(shared::Region, Section)>) -> Vec<(shared::Region, Section)> {
+    let sequence = exons
+        .iter()
+        .flat_map(|exon| exon.1 .0.iter().copied())
+        .collect_vec();
+    let motifs = sequence
+        .iter()
+        .tuple_windows()
+        .positions(|(a, b, _, d)| *a == AminoAcid::C && *b == AminoAcid::P && *d == AminoAcid::C)
+        .collect_vec();
+    let (Some(&start), Some(&last)) = (motifs.first(), motifs.last()) else {
+        return exons;
+    };
+    let mut end = last + 4;
+    if sequence.get(end) == Some(&AminoAcid::P) {
+        end += 1;
+    }
+    let with_part = |region, part| match region {
+        shared::Region::Hinge(exon, _) => shared::Region::Hinge(exon, Some(part)),
+        other => other,
+    };
+
+    let mut output = Vec::new();
+    let mut offset = 0;
+    for (region, exon) in &exons {
+        let mut rest = exon.clone();
+        for (part, boundary) in [
+            (HingePart::Upper, start),
+            (HingePart::Core, end),
+            (HingePart::Lower, usize::MAX),
+        ] {
+            let index = boundary.saturating_sub(offset);
+            if index == 0 {
+                continue;
+            }
+            if index >= rest.0.len() {
+                offset += rest.0.len();
+                output.push((with_part(*region, part), rest));
+                break;
+            }
+            let Some((left, right)) = split_section(&rest, index) else {
+                return exons;
+            };
+            offset += index;
+            output.push((with_part(*region, part), left));
+            rest = right;
+        }
+    }
+    output
+}
+
+fn fix_j(
+    j: Section,
+    cdr3_length: usize,
+) -> (Vec<(shared::Region, Section)>, Vec<(Annotation, usize)>) {
+    let Some((cdr3, fr4)) = split_section(&j, cdr3_length) else {
+        // No CDR3 residues are present in this J
+        return (vec![(shared::Region::FR4, j)], Vec::new());
+    };
+    let annotations = j_motif_annotations(&fr4.0, cdr3_length);
 
     (
         vec![(shared::Region::CDR3, cdr3), (shared::Region::FR4, fr4)],
@@ -991,24 +2149,137 @@ fn fix_j(
     )
 }
 
+/// Annotate the conserved residues of the W/F-G-x-G motif at the start of the FR4
+fn j_motif_annotations(fr4: &[AminoAcid], cdr3_length: usize) -> Vec<(Annotation, usize)> {
+    let mut annotations = Vec::new();
+    match fr4.first() {
+        Some(AminoAcid::W) => annotations.push((Annotation::Tryptophan, cdr3_length)),
+        Some(AminoAcid::F) => annotations.push((Annotation::Phenylalanine, cdr3_length)),
+        _ => (),
+    }
+    if fr4.get(1) == Some(&AminoAcid::G) {
+        annotations.push((Annotation::Glycine, cdr3_length + 1));
+    }
+    if fr4.get(3) == Some(&AminoAcid::G) {
+        annotations.push((Annotation::Glycine, cdr3_length + 3));
+    }
+    annotations
+}
+
+/// The number of FR4 residues used to build the consensus J motif
+const J_MOTIF_LENGTH: usize = 10;
+
+/// Split the J genes for which no CDR3/FR4 boundary could be determined (these are annotated as only FR4)
+/// by aligning them to the consensus FR4 motif of all other J genes of the same species and chain.
+fn split_j_genes(genes: &mut [(Species, SingleSeq)]) {
+    let mut motifs: HashMap<(Species, ChainType), Vec<Vec<AminoAcid>>> = HashMap::new();
+    for (species, gene) in genes.iter() {
+        if let [(shared::Region::CDR3, cdr3), (shared::Region::FR4, _)] = gene.sequence.regions[..]
+        {
+            if gene.name.gene == GeneType::J {
+                motifs.entry((*species, gene.name.chain)).or_default().push(
+                    gene.sequence.sequence.sequence[cdr3..]
+                        .iter()
+                        .take(J_MOTIF_LENGTH)
+                        .map(|s| s.aminoacid)
+                        .collect(),
+                );
+            }
+        }
+    }
+    let consensus: HashMap<(Species, ChainType), LinearPeptide> = motifs
+        .into_iter()
+        .map(|(key, motifs)| (key, consensus(&motifs).into()))
+        .collect();
+
+    for (species, gene) in genes.iter_mut() {
+        if gene.name.gene != GeneType::J
+            || gene.sequence.regions.len() != 1
+            || gene.sequence.regions[0].0 != shared::Region::FR4
+        {
+            continue;
+        }
+        let Some(consensus) = consensus.get(&(*species, gene.name.chain)) else {
+            continue;
+        };
+        let alignment = rustyms::align::align::<1>(
+            consensus,
+            &gene.sequence.sequence,
+            rustyms::align::BLOSUM62,
+            rustyms::Tolerance::new_absolute(Mass::new::<dalton>(0.01)),
+            rustyms::align::AlignType::LOCAL,
+        );
+        let boundary = alignment.start_b().saturating_sub(alignment.start_a());
+        let sequence = &mut gene.sequence;
+        let length = sequence.sequence.sequence.len();
+        if boundary == 0 || boundary >= length {
+            continue;
+        }
+        let dna_split = (boundary * 3).min(sequence.dna.len());
+        sequence.regions = vec![
+            (shared::Region::CDR3, boundary),
+            (shared::Region::FR4, length - boundary),
+        ];
+        sequence.dna_regions = vec![
+            (shared::Region::CDR3, dna_split),
+            (shared::Region::FR4, sequence.dna.len() - dna_split),
+        ];
+        let fr4 = sequence.sequence.sequence[boundary..]
+            .iter()
+            .map(|s| s.aminoacid)
+            .collect_vec();
+        sequence
+            .annotations
+            .extend(j_motif_annotations(&fr4, boundary));
+        sequence.annotations.sort_unstable_by_key(|a| a.1);
+    }
+}
+
+/// Get the most common amino acid for every position, as long as at least half of the motifs cover that position
+fn consensus(motifs: &[Vec<AminoAcid>]) -> Vec<AminoAcid> {
+    (0..J_MOTIF_LENGTH)
+        .map_while(|index| {
+            let column = motifs.iter().filter_map(|m| m.get(index)).collect_vec();
+            (column.len() * 2 >= motifs.len())
+                .then(|| {
+                    column
+                        .into_iter()
+                        .counts()
+                        .into_iter()
+                        .max_by_key(|(aa, count)| (*count, std::cmp::Reverse(aa.char())))
+                        .map(|(aa, _)| *aa)
+                })
+                .flatten()
+        })
+        .collect()
+}
+
 #[derive(Debug)]
 struct SingleSeq {
     name: Gene,
     allele: usize,
     acc: String,
     sequence: AnnotatedSequence,
-    dna: String,
 }
 
+/// The allele number, reading frame (only used for D genes), and isoform (only used for C genes) of an allele
+type AlleleKey = (usize, Option<usize>, Option<Isoform>);
+
 struct TemporaryGermline {
     name: Gene,
-    alleles: Vec<(usize, Vec<TemporarySequence>)>,
+    /// All alleles, identified by allele number, reading frame (only used for D genes), and isoform (only used for C genes)
+    alleles: Vec<(AlleleKey, Vec<TemporarySequence>)>,
 }
 
 impl TemporaryGermline {
     fn add(&mut self, single: SingleSeq) {
+        let key = (
+            single.allele,
+            single.sequence.reading_frame,
+            single.sequence.isoform,
+        );
         for al in &mut self.alleles {
-            if al.0 == single.allele {
+            if al.0 == key {
                 for s in &mut al.1 {
                     if s.sequence == single.sequence.sequence {
                         s.add_single(single);
@@ -1024,7 +2295,7 @@ impl TemporaryGermline {
         }
         // If not found
         self.alleles
-            .push((single.allele, vec![TemporarySequence::from_single(single)]));
+            .push((key, vec![TemporarySequence::from_single(single)]));
         self.alleles.sort_unstable_by_key(|a| a.0); // Maybe do the fancy insert at the right place trick
     }
 
@@ -1034,7 +2305,15 @@ impl TemporaryGermline {
             alleles: self
                 .alleles
                 .into_iter()
-                .map(|(a, seqs)| (a, seqs[0].annotated_sequence()))
+                .map(|((a, _, _), seqs)| {
+                    // Any other distinct sequences for this allele are kept as alternatives
+                    let mut sequence = seqs[0].annotated_sequence();
+                    sequence.alternatives = seqs[1..]
+                        .iter()
+                        .map(TemporarySequence::annotated_sequence)
+                        .collect();
+                    (a, sequence)
+                })
                 .collect(),
         }
     }
@@ -1047,7 +2326,14 @@ impl std::fmt::Display for TemporaryGermline {
         writeln!(f, "█ GENE: {}", self.name)?;
         let mut first_allele = None;
         for allele in &self.alleles {
-            writeln!(f, "╻ *{:02}", allele.0)?;
+            write!(f, "╻ *{:02}", allele.0 .0)?;
+            if let Some(frame) = allele.0 .1 {
+                write!(f, " (frame {frame})")?;
+            }
+            if let Some(isoform) = allele.0 .2 {
+                write!(f, " ({isoform})")?;
+            }
+            writeln!(f)?;
             let mut reference = None;
             for (index, seq) in allele.1.iter().enumerate() {
                 let main_branch = if index == allele.1.len() - 1 {
@@ -1164,6 +2450,7 @@ impl std::fmt::Display for TemporaryGermline {
                     for (di, dna) in seq.dna.iter().enumerate() {
                         let lines = dna
                             .0
+                             .0
                             .chars()
                             .collect_vec()
                             .chunks(MAX_WIDTH)
@@ -1209,13 +2496,24 @@ impl std::fmt::Display for TemporaryGermline {
     }
 }
 
+/// The coding DNA of a sequence with the length in nucleotides of every region
+type Dna = (String, Vec<(shared::Region, usize)>);
+
 #[derive(Debug, PartialEq, Eq)]
 struct TemporarySequence {
     acc: Vec<String>,
     sequence: LinearPeptide,
+    reading_frame: Option<usize>,
+    isoform: Option<Isoform>,
+    truncation: Truncation,
+    /// The allele label in the source, if it does not use IMGT names
+    label: Option<String>,
+    /// The leader peptide, from the first sequence that has it annotated
+    leader: Option<LinearPeptide>,
+    functionality: HashMap<Functionality, Vec<usize>>,
     regions: HashMap<Vec<(shared::Region, usize)>, Vec<usize>>,
     annotations: HashMap<Vec<(Annotation, usize)>, Vec<usize>>,
-    dna: HashMap<String, Vec<usize>>,
+    dna: HashMap<Dna, Vec<usize>>,
 }
 
 impl TemporarySequence {
@@ -1223,7 +2521,13 @@ impl TemporarySequence {
         Self {
             acc: vec![single.acc],
             sequence: single.sequence.sequence,
-            dna: [(single.dna, vec![0])].into(),
+            reading_frame: single.sequence.reading_frame,
+            isoform: single.sequence.isoform,
+            truncation: single.sequence.truncation,
+            label: single.sequence.provenance.label,
+            leader: single.sequence.leader,
+            functionality: [(single.sequence.functionality, vec![0])].into(),
+            dna: [((single.sequence.dna, single.sequence.dna_regions), vec![0])].into(),
             regions: [(single.sequence.regions, vec![0])].into(),
             annotations: [(single.sequence.annotations, vec![0])].into(),
         }
@@ -1232,7 +2536,17 @@ impl TemporarySequence {
     fn add_single(&mut self, single: SingleSeq) {
         let index = self.acc.len();
         self.acc.push(single.acc);
-        self.dna.entry(single.dna).or_default().push(index);
+        if self.leader.is_none() {
+            self.leader = single.sequence.leader;
+        }
+        self.functionality
+            .entry(single.sequence.functionality)
+            .or_default()
+            .push(index);
+        self.dna
+            .entry((single.sequence.dna, single.sequence.dna_regions))
+            .or_default()
+            .push(index);
         self.regions
             .entry(single.sequence.regions)
             .or_default()
@@ -1244,13 +2558,45 @@ impl TemporarySequence {
     }
 
     fn annotated_sequence(&self) -> AnnotatedSequence {
+        let (dna, dna_regions) = self.dna()[0].0.clone();
+        let (regions, regions_support) = self.regions().swap_remove(0);
+        let (annotations, annotations_support) = self.conserved().swap_remove(0);
+        let functionality = self
+            .functionality
+            .iter()
+            .max_by_key(|(f, a)| (a.len(), std::cmp::Reverse(**f)))
+            .map_or(Functionality::Functional, |(f, _)| *f);
         AnnotatedSequence {
             sequence: self.sequence.clone(),
-            regions: self.regions()[0].0.clone(),
-            annotations: self.conserved()[0].0.clone(),
+            regions,
+            annotations,
+            reading_frame: self.reading_frame,
+            isoform: self.isoform,
+            functionality,
+            dna,
+            dna_regions,
+            provenance: Provenance {
+                accessions: self.acc.clone(),
+                regions_support: regions_support.len(),
+                annotations_support: annotations_support.len(),
+                label: self.label.clone(),
+            },
+            truncation: self.truncation,
+            alternatives: Vec::new(),
+            leader: self.leader.clone(),
         }
     }
 
+    fn dna(&self) -> Vec<(Dna, Vec<usize>)> {
+        let mut vec = self
+            .dna
+            .iter()
+            .map(|(r, a)| (r.to_owned(), a.to_owned()))
+            .collect_vec();
+        vec.sort_by_key(|s| -(s.1.len() as isize));
+        vec
+    }
+
     fn regions(&self) -> Vec<(Vec<(shared::Region, usize)>, Vec<usize>)> {
         let mut vec = self
             .regions
@@ -1282,9 +2628,642 @@ impl PartialOrd for TemporarySequence {
 
 impl Ord for TemporarySequence {
     fn cmp(&self, other: &Self) -> std::cmp::Ordering {
-        other.acc.len().cmp(&self.acc.len()).then(
-            (other.regions()[0].0.len() + other.conserved()[0].0.len())
-                .cmp(&(self.regions()[0].0.len() + self.conserved()[0].0.len())),
+        // Complete sequences always go before partial sequences
+        let partial = self.truncation.is_partial();
+        partial
+            .cmp(&other.truncation.is_partial())
+            .then(other.acc.len().cmp(&self.acc.len()))
+            .then(
+                (other.regions()[0].0.len() + other.conserved()[0].0.len())
+                    .cmp(&(self.regions()[0].0.len() + self.conserved()[0].0.len())),
+            )
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::missing_panics_doc)]
+mod tests {
+    use super::*;
+
+    /// Build a loaded region with the given amino acid sequence
+    fn region(key: &str, location: &str, aa: &str) -> Region {
+        Region {
+            acc: "TEST".to_string(),
+            key: key.to_string(),
+            location: location.parse().unwrap(),
+            reported_seq: aa.to_string(),
+            found_seq: Some(Ok((
+                "nnn".repeat(aa.len()),
+                AASequence(
+                    aa.bytes()
+                        .map(|b| AminoAcid::try_from(b).unwrap())
+                        .collect(),
+                ),
+            ))),
+            allele: String::new(),
+            functionality: Some(Functionality::Functional),
+            partial: false,
+            truncation: Truncation::default(),
+            shift: 0,
+            splice_aa: None,
+            junction_dna: String::new(),
+        }
+    }
+
+    /// Build a partial V gene with the given IMGT regions
+    fn partial_v(location: &str, regions: &[(&str, &str)]) -> IMGTGene {
+        IMGTGene {
+            acc: "TEST".to_string(),
+            key: "V-GENE".to_string(),
+            location: location.parse().unwrap(),
+            allele: "IGHV1-2*01".to_string(),
+            functionality: Some(Functionality::Functional),
+            partial: true,
+            truncation: truncation(location),
+            regions: regions
+                .iter()
+                .map(|(key, aa)| (key.to_string(), region(key, "1..3", aa)))
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn partial_v_genes() {
+        let three_prime = partial_v(
+            "1..>60",
+            &[
+                ("FR1-IMGT", "QVQ"),
+                ("CDR1-IMGT", "GYT"),
+                ("FR2-IMGT", "MHW"),
+                ("CDR2-IMGT", "INP"),
+                ("FR3-IMGT", "YYC"),
+            ],
+        )
+        .finish()
+        .unwrap();
+        assert_eq!(three_prime.len(), 1);
+        assert_eq!(
+            three_prime[0].sequence.truncation,
+            Truncation {
+                five_prime: false,
+                three_prime: true
+            }
+        );
+        assert_eq!(three_prime[0].sequence.regions.len(), 5);
+
+        let five_prime = partial_v(
+            "<1..60",
+            &[
+                ("CDR1-IMGT", "GYT"),
+                ("FR2-IMGT", "MHW"),
+                ("CDR2-IMGT", "INP"),
+                ("FR3-IMGT", "YYC"),
+                ("CDR3-IMGT", "AR"),
+            ],
+        )
+        .finish()
+        .unwrap();
+        assert_eq!(
+            five_prime[0].sequence.truncation,
+            Truncation {
+                five_prime: true,
+                three_prime: false
+            }
+        );
+        assert_eq!(five_prime[0].sequence.regions[0], (shared::Region::CDR1, 3));
+
+        // Missing regions in the middle of the gene are not allowed
+        assert!(partial_v(
+            "<1..>60",
+            &[
+                ("FR1-IMGT", "QVQ"),
+                ("FR2-IMGT", "MHW"),
+                ("FR3-IMGT", "YYC")
+            ],
+        )
+        .finish()
+        .is_err());
+    }
+
+    #[test]
+    fn missing_functionality() {
+        let mut gene = partial_v(
+            "1..60",
+            &[
+                ("FR1-IMGT", "QVQ"),
+                ("CDR1-IMGT", "GYT"),
+                ("FR2-IMGT", "MHW"),
+                ("CDR2-IMGT", "INP"),
+                ("FR3-IMGT", "YYC"),
+                ("CDR3-IMGT", "AR"),
+            ],
+        );
+        gene.partial = false;
+        gene.functionality = None;
+        assert_eq!(
+            gene.finish().unwrap_err(),
+            GenerateError::MissingFunctionality
+        );
+    }
+
+    #[test]
+    fn unknown_species() {
+        let dat = "ID   TEST1; standard; DNA; IG; 6 BP.\n\
+                   KW   immunoglobulin (IG); germline;\n\
+                   OS   Homo erectus\n\
+                   SQ   Sequence 6 BP;\n     \
+                   caggtg\n\
+                   //\n";
+        let errors = parse_dat(
+            BufReader::new(dat.as_bytes()),
+            false,
+            TranslationCheck::Error,
+        )
+        .map(|item| item.map(|item| item.id))
+        .collect_vec();
+        assert_eq!(
+            errors,
+            [Err((
+                None,
+                "ID   TEST1; standard; DNA; IG; 6 BP.".to_string(),
+                GenerateError::UnknownSpecies("Homo erectus".to_string())
+            ))]
+        );
+    }
+
+    #[test]
+    fn stop_codons() {
+        let aa = |dna| {
+            translate(dna)
+                .unwrap()
+                .1
+                .iter()
+                .map(AminoAcid::char)
+                .collect::<String>()
+        };
+        // Internal stop codons keep their position, a final stop codon ends the protein
+        assert_eq!(aa("atgtaaggc"), "MXG");
+        assert_eq!(aa("atgggctaa"), "MG");
+        assert!(translate("atgnnn").is_err());
+
+        // D gene frames with a stop codon are skipped
+        assert_eq!(translate_d_frame("tagggtactgg", 0).unwrap(), None);
+        assert_eq!(
+            translate_d_frame("tagggtactgg", 1)
+                .unwrap()
+                .map(|(_, aa)| aa.iter().map(AminoAcid::char).collect::<String>()),
+            Some("RVL".to_string())
+        );
+        assert_eq!(
+            translate_d_frame("tagggtactgg", 2)
+                .unwrap()
+                .map(|(dna, _)| dna),
+            Some("gggtactgg")
+        );
+    }
+
+    #[test]
+    fn locations() {
+        assert_eq!(
+            "join(1..10,21..30)".parse::<Location>(),
+            Ok(Location::Join(vec![0..=9, 20..=29]))
+        );
+        assert_eq!(
+            "complement(join(1..10,21..30))".parse::<Location>(),
+            Ok(Location::ComplementJoin(vec![0..=9, 20..=29]))
+        );
+        assert_eq!(
+            "join(complement(21..30),complement(1..10))".parse::<Location>(),
+            Ok(Location::ComplementJoin(vec![0..=9, 20..=29]))
+        );
+        assert_eq!("<1..>30".parse::<Location>(), Ok(Location::Normal(0..=29)));
+        assert_eq!(
+            "complement(5)".parse::<Location>(),
+            Ok(Location::SingleComplement(4))
+        );
+        assert!("join(1..10,complement(21..30))"
+            .parse::<Location>()
+            .is_err());
+        assert!("join(1..10,21..30".parse::<Location>().is_err());
+
+        let join = Location::Join(vec![0..=9, 20..=29]);
+        assert_eq!(join.spliced_offset(20), Some(10));
+        assert_eq!(join.spliced_offset(15), None);
+        assert_eq!(
+            join.splice(4),
+            Some((
+                Location::Join(vec![0..=9, 20..=21]),
+                Location::Normal(22..=29)
+            ))
+        );
+        assert_eq!(join.splice(0), None);
+        assert_eq!(join.splice(7), None);
+
+        let complement = Location::ComplementJoin(vec![0..=9, 20..=29]);
+        assert_eq!(complement.first(), Some(29));
+        assert_eq!(complement.spliced_offset(9), Some(10));
+        assert_eq!(
+            complement.splice(4),
+            Some((
+                Location::ComplementJoin(vec![8..=9, 20..=29]),
+                Location::Complement(0..=7)
+            ))
+        );
+    }
+
+    #[test]
+    fn translation_mismatch() {
+        let item = |translation_check| DataItem {
+            id: "TEST".to_string(),
+            genes: Vec::new(),
+            regions: Vec::new(),
+            species: Species::HomoSapiens,
+            sequence: String::new(),
+            keep_partial: false,
+            translation_check,
+            warnings: Vec::new(),
+        };
+        let mismatch = GenerateError::TranslationMismatch("QVQ".to_string(), "QLQ".to_string());
+        let found = |region: &Region| match &region.found_seq {
+            Some(Ok((_, aa))) => Ok(aa.0.iter().map(|a| a.char()).collect::<String>()),
+            Some(Err(err)) => Err(err.clone()),
+            None => unreachable!(),
+        };
+
+        let mut matching = region("FR1-IMGT", "1..9", "QVQ");
+        matching.reported_seq = "QVQ".to_string();
+        let mut data = item(TranslationCheck::Error);
+        data.check_translation(&mut matching);
+        assert!(data.warnings.is_empty());
+        assert_eq!(found(&matching), Ok("QVQ".to_string()));
+
+        let mut data = item(TranslationCheck::Warn);
+        let mut warn = region("FR1-IMGT", "1..9", "QLQ");
+        warn.reported_seq = "QVQ".to_string();
+        data.check_translation(&mut warn);
+        assert_eq!(data.warnings, [("FR1-IMGT".to_string(), mismatch.clone())]);
+        assert_eq!(found(&warn), Ok("QLQ".to_string()));
+
+        let mut data = item(TranslationCheck::Error);
+        let mut error = region("FR1-IMGT", "1..9", "QLQ");
+        error.reported_seq = "QVQ".to_string();
+        data.check_translation(&mut error);
+        assert!(data.warnings.is_empty());
+        assert_eq!(found(&error), Err(mismatch.clone()));
+
+        let mut data = item(TranslationCheck::PreferReported);
+        let mut prefer = region("FR1-IMGT", "1..9", "QLQ");
+        prefer.reported_seq = "QVQ".to_string();
+        data.check_translation(&mut prefer);
+        assert_eq!(data.warnings, [("FR1-IMGT".to_string(), mismatch)]);
+        assert_eq!(found(&prefer), Ok("QVQ".to_string()));
+    }
+
+    /// Build a J gene with the given regions
+    fn j_gene(name: &str, sequence: &str, regions: Vec<(shared::Region, usize)>) -> SingleSeq {
+        let (name, allele) = Gene::from_imgt_name_with_allele(name).unwrap();
+        SingleSeq {
+            name,
+            allele,
+            acc: "TEST".to_string(),
+            sequence: AnnotatedSequence {
+                dna: "nnn".repeat(sequence.len()),
+                dna_regions: vec![(regions[regions.len() - 1].0, sequence.len() * 3)],
+                ..AnnotatedSequence::new(
+                    sequence
+                        .bytes()
+                        .map(|b| AminoAcid::try_from(b).unwrap())
+                        .collect_vec()
+                        .into(),
+                    regions,
+                    Vec::new(),
+                )
+            },
+        }
+    }
+
+    #[test]
+    fn j_consensus_split() {
+        let mut genes = vec![
+            (
+                Species::HomoSapiens,
+                j_gene(
+                    "IGHJ4*01",
+                    "YFDYWGQGTLVTVSS",
+                    vec![(shared::Region::CDR3, 4), (shared::Region::FR4, 11)],
+                ),
+            ),
+            (
+                Species::HomoSapiens,
+                j_gene(
+                    "IGHJ3*01",
+                    "AFDIWGQGTMVTVSS",
+                    vec![(shared::Region::CDR3, 4), (shared::Region::FR4, 11)],
+                ),
+            ),
+            (
+                Species::HomoSapiens,
+                j_gene(
+                    "IGHJ5*01",
+                    "NWFDPWGQGTLVTVSS",
+                    vec![(shared::Region::FR4, 16)],
+                ),
+            ),
+            // There is no consensus for the kappa chain, so this is kept as is
+            (
+                Species::HomoSapiens,
+                j_gene("IGKJ1*01", "WTFGQGTKVEIK", vec![(shared::Region::FR4, 12)]),
+            ),
+        ];
+        split_j_genes(&mut genes);
+        let split = &genes[2].1.sequence;
+        assert_eq!(
+            split.regions,
+            [(shared::Region::CDR3, 5), (shared::Region::FR4, 11)]
+        );
+        assert_eq!(
+            split.dna_regions,
+            [(shared::Region::CDR3, 15), (shared::Region::FR4, 33)]
+        );
+        assert_eq!(
+            split.annotations,
+            [
+                (Annotation::Tryptophan, 5),
+                (Annotation::Glycine, 6),
+                (Annotation::Glycine, 8)
+            ]
+        );
+        assert_eq!(genes[3].1.sequence.regions, [(shared::Region::FR4, 12)]);
+    }
+
+    /// Build a hinge exon with the given sequence, the location starts at the given nucleotide
+    fn hinge(exon: usize, aa: &str, start: usize) -> (shared::Region, Section) {
+        (
+            shared::Region::Hinge(exon, None),
+            (
+                aa.bytes()
+                    .map(|b| AminoAcid::try_from(b).unwrap())
+                    .collect(),
+                Location::Normal(start..=start + aa.len() * 3 - 1),
+                "nnn".repeat(aa.len()),
+            ),
         )
     }
+
+    /// The regions with their lengths of split hinge exons
+    fn parts(exons: &[(shared::Region, Section)]) -> Vec<(shared::Region, usize)> {
+        exons
+            .iter()
+            .map(|(region, section)| (*region, section.0.len()))
+            .collect()
+    }
+
+    #[test]
+    fn secretory_ch2() {
+        let gene = IMGTGene {
+            acc: "TEST".to_string(),
+            key: "C-GENE".to_string(),
+            location: "1..30".parse().unwrap(),
+            allele: "IGHD*01".to_string(),
+            functionality: Some(Functionality::Functional),
+            partial: false,
+            truncation: Truncation::default(),
+            regions: [
+                ("CH1", "1..9", "ASP"),
+                ("H", "10..18", "KTP"),
+                ("CH2-CHS", "19..30", "SVFK"),
+            ]
+            .into_iter()
+            .map(|(key, location, aa)| (key.to_string(), region(key, location, aa)))
+            .collect(),
+        };
+        let genes = gene.finish().unwrap();
+        assert_eq!(genes.len(), 1);
+        let regions = &genes[0].sequence.regions;
+        assert_eq!(regions.first(), Some(&(shared::Region::CH1, 3)));
+        assert_eq!(regions.last(), Some(&(shared::Region::CH2_CHS, 4)));
+        assert!(!regions.iter().any(|r| r.0 == shared::Region::CHS));
+    }
+
+    #[test]
+    fn hinge_parts() {
+        use shared::Region::Hinge;
+        // IgG1, the lower hinge (APELLGG) is part of CH2
+        let igg1 = split_hinge(vec![hinge(1, "EPKSCDKTHTCPPCP", 0)]);
+        assert_eq!(
+            parts(&igg1),
+            [
+                (Hinge(1, Some(HingePart::Upper)), 10),
+                (Hinge(1, Some(HingePart::Core)), 5)
+            ]
+        );
+        assert_eq!(igg1[1].1 .1, Location::Normal(30..=44));
+        assert_eq!(igg1[1].1 .2.len(), 15);
+
+        // IgG3, with the core hinge over four exons
+        let igg3 = split_hinge(vec![
+            hinge(1, "ELKTPLGDTTHTCPRCP", 0),
+            hinge(2, "EPKSCDTPPPCPRCP", 100),
+            hinge(3, "EPKSCDTPPPCPRCP", 200),
+            hinge(4, "EPKSCDTPPPCPRCP", 300),
+        ]);
+        assert_eq!(
+            parts(&igg3),
+            [
+                (Hinge(1, Some(HingePart::Upper)), 12),
+                (Hinge(1, Some(HingePart::Core)), 5),
+                (Hinge(2, Some(HingePart::Core)), 15),
+                (Hinge(3, Some(HingePart::Core)), 15),
+                (Hinge(4, Some(HingePart::Core)), 15)
+            ]
+        );
+
+        // IgD, without a core hinge motif
+        let igd = vec![
+            hinge(
+                1,
+                "ESPKAQASSVPTAQPQAEGSLAKATTAPATTRNTGRGGEEKKKEKEKEEQEERETKTP",
+                0,
+            ),
+            hinge(2, "ECPSHTQPLGVYLLTPAVQDLWLRDKATFTC", 300),
+        ];
+        assert_eq!(split_hinge(igd.clone()), igd);
+
+        // A core hinge followed by a lower hinge
+        let lower = split_hinge(vec![hinge(1, "EPKSCDKTHTCPPCPAPELLGG", 0)]);
+        assert_eq!(
+            parts(&lower),
+            [
+                (Hinge(1, Some(HingePart::Upper)), 10),
+                (Hinge(1, Some(HingePart::Core)), 5),
+                (Hinge(1, Some(HingePart::Lower)), 7)
+            ]
+        );
+
+        // The exons are kept whole if the location cannot be split
+        let mut broken = hinge(1, "EPKSCDKTHTCPPCP", 0);
+        broken.1 .1 = Location::Normal(0..=20);
+        assert_eq!(split_hinge(vec![broken.clone()]), [broken]);
+    }
+
+    #[test]
+    fn glycans() {
+        let aa = |s: &str| {
+            s.bytes()
+                .map(|b| AminoAcid::try_from(b).unwrap())
+                .collect_vec()
+        };
+        let sequence = aa("EPKSCDKTHTCPPCPNPSAQYNSTYNGCKNQT");
+        assert_eq!(
+            find_glycan_locations(
+                &sequence,
+                &[
+                    (shared::Region::Hinge(1, None), 15),
+                    (shared::Region::CH2, 17)
+                ],
+                true
+            ),
+            [
+                (Annotation::NGlycanOccupied, 21),
+                (Annotation::NGlycanAtypical, 25),
+                (Annotation::NGlycan, 29),
+                (Annotation::OGlycan, 3),
+                (Annotation::OGlycan, 9)
+            ]
+        );
+        // N-x-C sequons are only annotated when asked for
+        assert_eq!(
+            find_glycan_locations(&aa("ANGCA"), &[(shared::Region::CH1, 5)], false),
+            []
+        );
+        assert_eq!(
+            find_glycan_locations(&aa("ANGCA"), &[(shared::Region::CH1, 5)], true),
+            [(Annotation::NGlycanAtypical, 1)]
+        );
+        // The Q-Y-N-S-T sequon is only known to be occupied in the CH2
+        assert_eq!(
+            find_glycan_locations(&aa("AQYNSTY"), &[(shared::Region::CH1, 7)], false),
+            [(Annotation::NGlycan, 3)]
+        );
+        // For a combined hinge and CH2 exon the hinge runs up to the first cysteine
+        assert_eq!(
+            find_glycan_locations(
+                &aa("VPSTPPTPSPSCCHPRLSL"),
+                &[(shared::Region::H_CH2, 19)],
+                false
+            ),
+            [2, 3, 6, 8, 10].map(|index| (Annotation::OGlycan, index))
+        );
+    }
+
+    #[test]
+    fn disulfides() {
+        use shared::Region::{Hinge, CH1, CH2, CL, FR1, FR3};
+        let aa = |s: &str| {
+            s.bytes()
+                .map(|b| AminoAcid::try_from(b).unwrap())
+                .collect_vec()
+        };
+        let intra = |a, b| {
+            [
+                (Annotation::Disulfide(DisulfidePartner::Intra(b)), a),
+                (Annotation::Disulfide(DisulfidePartner::Intra(a)), b),
+            ]
+        };
+        // A V domain, CH1 without an unpaired cysteine, IgG1 like hinge, and CH2
+        let ch1 = format!("{}C{}C{}", "A".repeat(10), "A".repeat(59), "A".repeat(5));
+        let ch2 = format!("C{}CAA", "A".repeat(60));
+        let heavy = aa(&format!("QVQCAAYC{ch1}EPKSCDKTHTCPPCP{ch2}"));
+        let found = find_disulfides(
+            &heavy,
+            &[
+                (FR1, 4),
+                (FR3, 4),
+                (CH1, 76),
+                (Hinge(1, Some(HingePart::Upper)), 10),
+                (Hinge(1, Some(HingePart::Core)), 5),
+                (CH2, 64),
+            ],
+            &[(Annotation::Cysteine1, 3), (Annotation::Cysteine2, 7)],
+            ChainType::Heavy,
+        );
+        assert_eq!(
+            found,
+            [
+                intra(3, 7).to_vec(),
+                intra(18, 78).to_vec(),
+                intra(99, 160).to_vec(),
+                vec![
+                    (Annotation::Disulfide(DisulfidePartner::LightChain), 88),
+                    (Annotation::Disulfide(DisulfidePartner::HeavyChain), 94),
+                    (Annotation::Disulfide(DisulfidePartner::HeavyChain), 97)
+                ],
+            ]
+            .concat()
+        );
+
+        // An unpaired cysteine in the CH1 bonds the light chain instead, IGHG2 CH1 and hinge
+        let found = find_disulfides(
+            &aa("ASTKGPSVFPLAPCSRSTSESTAALGCLVKDYFPEPVTVSWNSGALTSGVHTFPAVLQSSGLYSLSSVVTVPSSNFGTQTYTCNVDHKPSNTKVDKTVERKCCVECPPCP"),
+            &[
+                (CH1, 98),
+                (Hinge(1, Some(HingePart::Upper)), 7),
+                (Hinge(1, Some(HingePart::Core)), 5),
+            ],
+            &[],
+            ChainType::Heavy,
+        );
+        assert_eq!(
+            found,
+            [
+                intra(26, 82).to_vec(),
+                vec![
+                    (Annotation::Disulfide(DisulfidePartner::LightChain), 13),
+                    (Annotation::Disulfide(DisulfidePartner::HeavyChain), 101),
+                    (Annotation::Disulfide(DisulfidePartner::HeavyChain), 102),
+                    (Annotation::Disulfide(DisulfidePartner::HeavyChain), 105),
+                    (Annotation::Disulfide(DisulfidePartner::HeavyChain), 108)
+                ],
+            ]
+            .concat()
+        );
+
+        // The last unpaired cysteine of the CL bonds the heavy chain, IGKC
+        let found = find_disulfides(
+            &aa("RTVAAPSVFIFPPSDEQLKSGTASVVCLLNNFYPREAKVQWKVDNALQSGNSQESVTEQDSKDSTYSLSSTLTLSKADYEKHKVYACEVTHQGLSSPVTKSFNRGEC"),
+            &[(CL, 107)],
+            &[],
+            ChainType::LightKappa,
+        );
+        assert_eq!(
+            found,
+            [
+                intra(26, 86).to_vec(),
+                vec![(Annotation::Disulfide(DisulfidePartner::HeavyChain), 106)]
+            ]
+            .concat()
+        );
+
+        // T cell receptor constant regions are labelled CL but do not bond an immunoglobulin heavy chain
+        let constant = aa(&format!(
+            "{}C{}C{}C{}",
+            "A".repeat(10),
+            "A".repeat(59),
+            "A".repeat(20),
+            "A".repeat(5)
+        ));
+        let regions = [(CL, constant.len())];
+        assert_eq!(
+            find_disulfides(&constant, &regions, &[], ChainType::Beta),
+            intra(10, 70)
+        );
+        assert_eq!(
+            find_disulfides(&constant, &regions, &[], ChainType::LightLambda),
+            [
+                intra(10, 70).to_vec(),
+                vec![(Annotation::Disulfide(DisulfidePartner::HeavyChain), 91)]
+            ]
+            .concat()
+        );
+    }
 }
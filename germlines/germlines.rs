@@ -1,6 +1,9 @@
 #![allow(non_snake_case,non_upper_case_globals)]
 use std::sync::OnceLock;
 use crate::shared::{Germlines, Species};
+use crate::DatabaseInfo;
+/// The database release these germlines were generated from
+pub const DATABASE_INFO: DatabaseInfo = DatabaseInfo {release: "unknown", date: "unknown", generator_version: "0.1.0", checksum: "unknown"};
 /// Get the germlines for any of the available species. See the main documentation for which species have which data available.
 pub fn germlines(species: Species) -> Option<&'static Germlines> {match species {
 Species::BosTaurus => Some(lock_BosTaurus()),
@@ -0,0 +1,204 @@
+use std::io::BufRead;
+
+use rustyms::{
+    align::{AlignType, OwnedAlignment},
+    AminoAcid, LinearPeptide, Tolerance,
+};
+
+use crate::{shared::*, Allele, Selection};
+
+/// A germline set loaded at runtime from user supplied sequences.
+///
+/// This can be used for species that are not covered by IMGT or for transgenic animals. The set is
+/// identified by the label given by the user, its alleles have no species.
+///
+/// ```
+/// use rustyms_imgt::*;
+/// let fasta = ">IGHJ4*01 CDR3:4 FR4:11\nYFDYWGQGTLVTVSS\n";
+/// let germlines = CustomGermlines::from_fasta("Humanized mouse", fasta.as_bytes()).unwrap();
+/// let first = germlines.germlines(Selection::default()).next().unwrap();
+/// assert_eq!(first.name(), "IGHJ4*01");
+/// assert_eq!(first.species, None);
+/// ```
+#[derive(Debug)]
+pub struct CustomGermlines {
+    label: String,
+    germlines: Germlines,
+}
+
+impl CustomGermlines {
+    /// Load a germline set from a FASTA file with amino acid sequences. Every header has to start with
+    /// the IMGT style allele name (eg `>IGHV1-2*01`), optionally followed by the regions as whitespace
+    /// separated region names with their length in amino acids (eg `FR1:25 CDR1:8 FR2:17 CDR2:8 FR3:38 CDR3:2`).
+    /// If the regions are given they have to cover the whole sequence. A leader peptide can be given as the
+    /// first region (eg `Leader:19`), this is stored separately from the mature sequence (see [`Allele::leader`]).
+    /// All alleles are marked as functional.
+    /// # Errors
+    /// If the file could not be read, or if any of the records could not be parsed.
+    pub fn from_fasta(label: impl Into<String>, reader: impl BufRead) -> Result<Self, String> {
+        let mut germlines = Germlines::custom();
+        let mut record: Option<(String, String)> = None;
+        for line in reader.lines() {
+            let line = line.map_err(|e| format!("Could not read the custom germlines: {e}"))?;
+            if let Some(header) = line.strip_prefix('>') {
+                if let Some((header, sequence)) =
+                    record.replace((header.trim().to_string(), String::new()))
+                {
+                    germlines.insert(parse_record(&header, &sequence)?);
+                }
+            } else if let Some((_, sequence)) = &mut record {
+                sequence.push_str(line.trim());
+            } else if !line.trim().is_empty() {
+                return Err("The custom germlines should start with a FASTA header".to_string());
+            }
+        }
+        if let Some((header, sequence)) = record {
+            germlines.insert(parse_record(&header, &sequence)?);
+        }
+        Ok(Self {
+            label: label.into(),
+            germlines,
+        })
+    }
+
+    /// The label given to this set
+    pub fn label(&self) -> &str {
+        &self.label
+    }
+
+    /// Get a specific germline from this set, see [`crate::get_germline`]
+    pub fn get(&self, gene: Gene, allele: Option<usize>) -> Vec<Allele<'_>> {
+        self.germlines.find(gene, allele)
+    }
+
+    /// Get the selected alleles from this set. The alleles have no species, so the species selection
+    /// should be left empty.
+    pub fn germlines(&self, selection: Selection) -> impl Iterator<Item = Allele<'_>> {
+        selection.select_from(std::iter::once(&self.germlines))
+    }
+
+    /// Align one sequence to multiple consecutive genes from this set, see [`crate::consecutive_align`].
+    /// The chains and allele selection are taken from the given selection.
+    pub fn consecutive_align<const STEPS: u16>(
+        &self,
+        sequence: &LinearPeptide,
+        genes: &[(GeneType, AlignType)],
+        selection: Selection,
+        tolerance: Tolerance,
+        matrix: &[[i8; AminoAcid::TOTAL_NUMBER]; AminoAcid::TOTAL_NUMBER],
+        return_number: usize,
+    ) -> Vec<Vec<(Allele<'_>, OwnedAlignment)>> {
+        crate::align_consecutive::<STEPS, _>(
+            sequence,
+            genes,
+            selection,
+            |selection| self.germlines(selection),
+            tolerance,
+            matrix,
+            return_number,
+        )
+    }
+}
+
+/// Parse a single FASTA record into a germline with a single allele
+/// # Errors
+/// If the name, regions, or sequence are invalid, or if the regions do not cover the sequence.
+fn parse_record(header: &str, sequence: &str) -> Result<Germline, String> {
+    let mut fields = header.split_whitespace();
+    let name = fields
+        .next()
+        .ok_or_else(|| "Empty FASTA header in the custom germlines".to_string())?;
+    let (gene, allele) = Gene::from_imgt_name_with_allele(name)?;
+    let mut regions = fields
+        .map(|field| {
+            let (region, length) = field.split_once(':').ok_or_else(|| {
+                format!("Invalid region `{field}` for {name}, use REGION:LENGTH (eg `FR1:25`)")
+            })?;
+            Ok((
+                region
+                    .parse::<Region>()
+                    .map_err(|()| format!("Unknown region `{region}` for {name}"))?,
+                length
+                    .parse::<usize>()
+                    .map_err(|e| format!("Invalid length `{length}` for {name}: {e}"))?,
+            ))
+        })
+        .collect::<Result<Vec<_>, String>>()?;
+    let mut sequence = sequence
+        .bytes()
+        .map(|b| {
+            AminoAcid::try_from(b)
+                .map_err(|()| format!("Invalid amino acid `{}` for {name}", char::from(b)))
+        })
+        .collect::<Result<Vec<_>, String>>()?;
+    let length = regions.iter().map(|r| r.1).sum::<usize>();
+    if !regions.is_empty() && length != sequence.len() {
+        return Err(format!(
+            "The regions for {name} sum to {length} residues but the sequence has {} residues",
+            sequence.len()
+        ));
+    }
+    if regions.iter().skip(1).any(|r| r.0 == Region::Leader) {
+        return Err(format!("The leader has to be the first region for {name}"));
+    }
+    // The leader is stored separately from the mature sequence
+    let leader = if regions.first().is_some_and(|r| r.0 == Region::Leader) {
+        let (_, length) = regions.remove(0);
+        Some(sequence.drain(..length).collect::<Vec<_>>().into())
+    } else {
+        None
+    };
+    Ok(Germline {
+        name: gene,
+        alleles: vec![(
+            allele,
+            AnnotatedSequence {
+                leader,
+                ..AnnotatedSequence::new(sequence.into(), regions, Vec::new())
+            },
+        )],
+    })
+}
+
+#[allow(clippy::missing_panics_doc)]
+#[test]
+fn custom_errors() {
+    let error = |fasta: &str| CustomGermlines::from_fasta("Test", fasta.as_bytes()).unwrap_err();
+    assert_eq!(
+        error("QVQLV\n>IGHV1-2*01\nQVQLV\n"),
+        "The custom germlines should start with a FASTA header"
+    );
+    assert_eq!(
+        error(">\nQVQLV\n"),
+        "Empty FASTA header in the custom germlines"
+    );
+    assert!(!error(">XYZ1*01\nQVQLV\n").is_empty());
+    assert_eq!(
+        error(">IGHV1-2*01 FR1\nQVQLV\n"),
+        "Invalid region `FR1` for IGHV1-2*01, use REGION:LENGTH (eg `FR1:25`)"
+    );
+    assert_eq!(
+        error(">IGHV1-2*01 FR9:5\nQVQLV\n"),
+        "Unknown region `FR9` for IGHV1-2*01"
+    );
+    assert!(
+        error(">IGHV1-2*01 FR1:five\nQVQLV\n").starts_with("Invalid length `five` for IGHV1-2*01")
+    );
+    assert_eq!(
+        error(">IGHV1-2*01 FR1:3 CDR1:3\nQVQLV\n"),
+        "The regions for IGHV1-2*01 sum to 6 residues but the sequence has 5 residues"
+    );
+    assert_eq!(
+        error(">IGHV1-2*01 FR1:3 Leader:2\nQVQLV\n"),
+        "The leader has to be the first region for IGHV1-2*01"
+    );
+    assert_eq!(
+        error(">IGHV1-2*01\nQVQ1V\n"),
+        "Invalid amino acid `1` for IGHV1-2*01"
+    );
+    // Errors in later records are found as well
+    assert_eq!(
+        error(">IGHV1-2*01 FR1:5\nQVQLV\n>IGHV1-3*01 FR1:4\nQVQLV\n"),
+        "The regions for IGHV1-3*01 sum to 4 residues but the sequence has 5 residues"
+    );
+}
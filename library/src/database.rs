@@ -0,0 +1,18 @@
+/// Information on the database release the germlines were generated from, use this to cite the exact germline release
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct DatabaseInfo {
+    /// The database release, eg `202345-1` for IMGT LIGM-DB, as given when generating the germlines
+    /// (or the release version of an AIRR germline set), `unknown` if it was not given
+    pub release: &'static str,
+    /// The date of the release, eg `12-NOV-2023`, `unknown` if it was not given
+    pub date: &'static str,
+    /// The version of the generator that was used to generate the germlines
+    pub generator_version: &'static str,
+    /// The SHA-256 checksum of the input file (as downloaded, so possibly compressed)
+    pub checksum: &'static str,
+}
+
+/// Get the information on the database release the germlines were generated from
+pub const fn database_info() -> &'static DatabaseInfo {
+    &crate::germlines::DATABASE_INFO
+}
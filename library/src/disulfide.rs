@@ -0,0 +1,162 @@
+use crate::{Allele, Annotation, DisulfidePartner};
+
+/// A chain in an assembled antibody of two heavy and two light chains, the first light chain is paired
+/// with the first heavy chain and the second light chain with the second heavy chain
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum AntibodyChain {
+    /// The first heavy chain
+    Heavy1,
+    /// The second heavy chain
+    Heavy2,
+    /// The first light chain, paired with the first heavy chain
+    Light1,
+    /// The second light chain, paired with the second heavy chain
+    Light2,
+}
+
+/// A disulfide bond in an assembled antibody, both cysteines are given as the chain and the index in that chain
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct DisulfideBond {
+    /// The first cysteine of the bond
+    pub first: (AntibodyChain, usize),
+    /// The second cysteine of the bond
+    pub second: (AntibodyChain, usize),
+}
+
+/// Get the expected disulfide bonds of an assembled antibody with two identical heavy and two identical
+/// light chains, based on the [`Annotation::Disulfide`] annotations of the alleles.
+///
+/// The chains are given as the alleles of their genes in order (eg `[V, D, J, C]` for the heavy chain and
+/// `[V, J, C]` for the light chain), the indices in the bonds are into the concatenation of the sequences of
+/// these alleles. The cysteines bonding the light chain on the heavy chain and the cysteines bonding the heavy
+/// chain on the light chain are paired in order, the cysteines bonding the other heavy chain are paired with
+/// the same cysteine on the other heavy chain.
+pub fn disulfide_bonds(heavy: &[Allele<'_>], light: &[Allele<'_>]) -> Vec<DisulfideBond> {
+    let (heavy_intra, heavy_light, heavy_heavy) = chain_disulfides(heavy);
+    let (light_intra, _, light_heavy) = chain_disulfides(light);
+    let mut bonds = Vec::new();
+    for (heavy_chain, light_chain) in [
+        (AntibodyChain::Heavy1, AntibodyChain::Light1),
+        (AntibodyChain::Heavy2, AntibodyChain::Light2),
+    ] {
+        bonds.extend(heavy_intra.iter().map(|(a, b)| DisulfideBond {
+            first: (heavy_chain, *a),
+            second: (heavy_chain, *b),
+        }));
+        bonds.extend(light_intra.iter().map(|(a, b)| DisulfideBond {
+            first: (light_chain, *a),
+            second: (light_chain, *b),
+        }));
+        bonds.extend(
+            heavy_light
+                .iter()
+                .zip(&light_heavy)
+                .map(|(h, l)| DisulfideBond {
+                    first: (heavy_chain, *h),
+                    second: (light_chain, *l),
+                }),
+        );
+    }
+    bonds.extend(heavy_heavy.iter().map(|index| DisulfideBond {
+        first: (AntibodyChain::Heavy1, *index),
+        second: (AntibodyChain::Heavy2, *index),
+    }));
+    bonds
+}
+
+/// Get the intra chain bonds, the cysteines bonding the light chain, and the cysteines bonding a heavy chain
+/// for a chain built from the given alleles
+fn chain_disulfides(alleles: &[Allele<'_>]) -> (Vec<(usize, usize)>, Vec<usize>, Vec<usize>) {
+    let mut intra = Vec::new();
+    let mut light = Vec::new();
+    let mut heavy = Vec::new();
+    let mut offset = 0;
+    for allele in alleles {
+        for (annotation, index) in allele.annotations {
+            match annotation {
+                Annotation::Disulfide(DisulfidePartner::Intra(partner)) if index < partner => {
+                    intra.push((offset + index, offset + partner));
+                }
+                Annotation::Disulfide(DisulfidePartner::LightChain) => light.push(offset + index),
+                Annotation::Disulfide(DisulfidePartner::HeavyChain) => heavy.push(offset + index),
+                _ => (),
+            }
+        }
+        offset += allele.sequence.len();
+    }
+    (intra, light, heavy)
+}
+
+#[allow(clippy::missing_panics_doc)]
+#[test]
+fn igg1_kappa() {
+    use crate::{AnnotatedSequence, Gene, Germline, Germlines, Species};
+    use AntibodyChain::*;
+
+    let mut germlines = Germlines::new(Species::HomoSapiens);
+    for (name, sequence, annotations) in [
+        (
+            "IGHV1-2*01",
+            "ACAAAC",
+            vec![
+                (Annotation::Disulfide(DisulfidePartner::Intra(5)), 1),
+                (Annotation::Disulfide(DisulfidePartner::Intra(1)), 5),
+            ],
+        ),
+        (
+            "IGHG1*01",
+            "AAAAAAAAAAEPKSCDKTHTCPPCP",
+            vec![
+                (Annotation::Disulfide(DisulfidePartner::LightChain), 14),
+                (Annotation::Disulfide(DisulfidePartner::HeavyChain), 20),
+                (Annotation::Disulfide(DisulfidePartner::HeavyChain), 23),
+            ],
+        ),
+        (
+            "IGKV1-5*01",
+            "DCAAAC",
+            vec![
+                (Annotation::Disulfide(DisulfidePartner::Intra(5)), 1),
+                (Annotation::Disulfide(DisulfidePartner::Intra(1)), 5),
+            ],
+        ),
+        (
+            "IGKC*01",
+            "RTVAAPSVNRGEC",
+            vec![(Annotation::Disulfide(DisulfidePartner::HeavyChain), 12)],
+        ),
+    ] {
+        let (name, allele) = Gene::from_imgt_name_with_allele(name).unwrap();
+        let sequence = sequence
+            .bytes()
+            .map(|b| rustyms::AminoAcid::try_from(b).unwrap())
+            .collect::<Vec<_>>();
+        germlines.insert(Germline {
+            name,
+            alleles: vec![(
+                allele,
+                AnnotatedSequence::new(sequence.into(), Vec::new(), annotations),
+            )],
+        });
+    }
+    let get = |name| {
+        let (gene, allele) = Gene::from_imgt_name_with_allele(name).unwrap();
+        germlines.find(gene, Some(allele)).remove(0)
+    };
+    let heavy = [get("IGHV1-2*01"), get("IGHG1*01")];
+    let light = [get("IGKV1-5*01"), get("IGKC*01")];
+    let bond = |first, second| DisulfideBond { first, second };
+    assert_eq!(
+        disulfide_bonds(&heavy, &light),
+        [
+            bond((Heavy1, 1), (Heavy1, 5)),
+            bond((Light1, 1), (Light1, 5)),
+            bond((Heavy1, 20), (Light1, 18)),
+            bond((Heavy2, 1), (Heavy2, 5)),
+            bond((Light2, 1), (Light2, 5)),
+            bond((Heavy2, 20), (Light2, 18)),
+            bond((Heavy1, 26), (Heavy2, 26)),
+            bond((Heavy1, 29), (Heavy2, 29)),
+        ]
+    );
+}
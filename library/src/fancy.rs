@@ -17,7 +17,12 @@ impl FancyDisplay for Gene {
 
         write!(
             f,
-            "Ig{}{}{}{}",
+            "{}{}{}{}{}",
+            if self.chain.is_t_cell_receptor() {
+                "TR"
+            } else {
+                "Ig"
+            },
             self.chain.to_fancy_string(),
             self.gene.to_fancy_string(),
             self.number
@@ -34,7 +39,7 @@ impl FancyDisplay for Gene {
         let mut first = true;
         let mut last_str = false;
         for element in &self.family {
-            if !first && !last_str {
+            if !first && !last_str && !element.1.starts_with('/') {
                 write!(f, "-").unwrap();
             }
             write!(
@@ -58,6 +63,10 @@ impl FancyDisplay for ChainType {
             Self::LightKappa => "κ",
             Self::LightLambda => "λ",
             Self::Iota => "ι",
+            Self::Alpha => "α",
+            Self::Beta => "β",
+            Self::Gamma => "γ",
+            Self::Delta => "δ",
         }
         .to_string()
     }
@@ -67,6 +76,7 @@ impl FancyDisplay for GeneType {
     fn to_fancy_string(&self) -> String {
         match self {
             Self::V => "V",
+            Self::D => "D",
             Self::J => "J",
             Self::C(None) => "C",
             Self::C(Some(Constant::A)) => "α",
@@ -1,6 +1,7 @@
 //! This crate handles parsing the [IMGT LIGM-DB database](https://www.imgt.org/) into structures compatible with rustyms.
 //! It additionally stores all regions and annotations. There are two main ways of selecting germline(s), specified by name
 //! [`get_germline`] or by building a query over the data [`Selection`].
+//! Germlines for species or transgenic animals not covered by IMGT can be loaded at runtime with [`CustomGermlines`].
 //!
 //! <details><summary>Data present per species</summary>
 //!
@@ -30,6 +31,9 @@
     clippy::too_many_lines
 )]
 
+mod custom;
+mod database;
+mod disulfide;
 mod fancy;
 #[path = "../../germlines/germlines.rs"]
 mod germlines;
@@ -40,6 +44,9 @@ mod shared;
 use itertools_extension::*;
 use std::collections::HashSet;
 
+pub use custom::*;
+pub use database::*;
+pub use disulfide::*;
 pub use fancy::*;
 use germlines::{all_germlines, germlines, par_germlines};
 use itertools::Itertools;
@@ -52,6 +59,7 @@ pub use shared::*;
 
 /// Align one sequence to multiple consecutive genes. Each gene can be controlled to be global to the left or free to allow unmatched residues between it and the previous gene.
 /// If the sequence is too short to cover all genes only the genes that could be matched are returned.
+/// D genes are aligned in all reading frames without a stop codon, so a full heavy chain can be aligned with `[V, D, J, C]`.
 pub fn consecutive_align<const STEPS: u16>(
     sequence: &LinearPeptide,
     genes: &[(GeneType, AlignType)],
@@ -62,19 +70,49 @@ pub fn consecutive_align<const STEPS: u16>(
     matrix: &[[i8; AminoAcid::TOTAL_NUMBER]; AminoAcid::TOTAL_NUMBER],
     return_number: usize,
 ) -> Vec<Vec<(Allele<'static>, OwnedAlignment)>> {
+    align_consecutive::<STEPS, _>(
+        sequence,
+        genes,
+        Selection {
+            species,
+            chains,
+            allele,
+            ..Selection::default()
+        },
+        Selection::germlines,
+        tolerance,
+        matrix,
+        return_number,
+    )
+}
+
+/// Align one sequence to multiple consecutive genes, the germlines for each gene are retrieved with the
+/// given function from the selection with the species and gene filled in.
+fn align_consecutive<'a, const STEPS: u16, I: Iterator<Item = Allele<'a>>>(
+    sequence: &LinearPeptide,
+    genes: &[(GeneType, AlignType)],
+    selection: Selection,
+    germlines: impl Fn(Selection) -> I,
+    tolerance: Tolerance,
+    matrix: &[[i8; AminoAcid::TOTAL_NUMBER]; AminoAcid::TOTAL_NUMBER],
+    return_number: usize,
+) -> Vec<Vec<(Allele<'a>, OwnedAlignment)>> {
     assert!(genes.len() >= 2);
-    let mut output: Vec<Vec<(Allele<'static>, OwnedAlignment)>> = Vec::with_capacity(genes.len());
+    let mut output: Vec<Vec<(Allele<'a>, OwnedAlignment)>> = Vec::with_capacity(genes.len());
 
     let mut prev = 0;
     for n in 0..genes.len() {
         let (left_sequence, use_species) = if n == 0 {
-            (sequence.clone(), species.clone())
+            (sequence.clone(), selection.species.clone())
         } else {
             prev += output[n - 1][0].1.start_b() + output[n - 1][0].1.len_b();
             let mut left_sequence: LinearPeptide =
                 sequence.clone().sequence.into_iter().skip(prev).collect();
             left_sequence.c_term = sequence.c_term.clone();
-            (left_sequence, Some([output[n - 1][0].0.species].into()))
+            (
+                left_sequence,
+                output[n - 1][0].0.species.map(|species| [species].into()),
+            )
         };
 
         if left_sequence.is_empty() {
@@ -82,13 +120,11 @@ pub fn consecutive_align<const STEPS: u16>(
         }
 
         output.push(
-            Selection {
+            germlines(Selection {
                 species: use_species,
-                chains: chains.clone(),
-                allele: allele.clone(),
                 genes: Some([genes[n].0].into()),
-            }
-            .germlines()
+                ..selection.clone()
+            })
             .map(|seq| {
                 let alignment = rustyms::align::align::<STEPS>(
                     seq.sequence,
@@ -109,6 +145,7 @@ pub fn consecutive_align<const STEPS: u16>(
 
 /// Align one sequence to multiple consecutive genes. Each gene can be controlled to be global to the left or free to allow unmatched residues between it and the previous gene.
 /// If the sequence is too short to cover all genes only the genes that could be matched are returned.
+/// D genes are aligned in all reading frames without a stop codon, so a full heavy chain can be aligned with `[V, D, J, C]`.
 #[cfg(feature = "rayon")]
 pub fn par_consecutive_align<const STEPS: u16>(
     sequence: &LinearPeptide,
@@ -134,7 +171,10 @@ pub fn par_consecutive_align<const STEPS: u16>(
             let mut left_sequence: LinearPeptide =
                 sequence.clone().sequence.into_iter().skip(prev).collect();
             left_sequence.c_term = sequence.c_term.clone();
-            (left_sequence, Some([output[n - 1][0].0.species].into()))
+            (
+                left_sequence,
+                output[n - 1][0].0.species.map(|species| [species].into()),
+            )
         };
 
         if left_sequence.is_empty() {
@@ -147,6 +187,7 @@ pub fn par_consecutive_align<const STEPS: u16>(
                 chains: chains.clone(),
                 allele: allele.clone(),
                 genes: Some([genes[n].0].into()),
+                ..Selection::default()
             }
             .par_germlines()
             .map(|seq| {
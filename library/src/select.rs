@@ -2,18 +2,16 @@
 use rayon::prelude::*;
 use std::collections::HashSet;
 
+use itertools::Itertools;
 use rustyms::LinearPeptide;
 
 pub use crate::fancy::FancyDisplay;
 pub use crate::shared::*;
 
-/// Get a specific germline
-pub fn get_germline(
-    species: Species,
-    gene: Gene,
-    allele: Option<usize>,
-) -> Option<Allele<'static>> {
-    crate::germlines(species).and_then(|g| g.find(species, gene, allele))
+/// Get a specific germline, if no allele is given the first allele is returned. All sequences stored for this
+/// allele are returned: every reading frame for D genes and every isoform for constant genes.
+pub fn get_germline(species: Species, gene: Gene, allele: Option<usize>) -> Vec<Allele<'static>> {
+    crate::germlines(species).map_or_else(Vec::new, |g| g.find(gene, allele))
 }
 
 /// The selection rules for iterating over a selection of germlines.
@@ -25,8 +23,19 @@ pub struct Selection {
     pub chains: Option<HashSet<ChainType>>,
     /// The kind of genes you want, None allows all, otherwise only the genes specified will be returned
     pub genes: Option<HashSet<GeneType>>,
+    /// The isoforms of constant genes you want, None allows all, otherwise only the isoforms specified will be returned.
+    /// Genes without isoforms (V/D/J) are always returned. The default only returns the secreted form.
+    pub isoforms: Option<HashSet<Isoform>>,
+    /// The functionality of the alleles you want, None allows all, otherwise only the functionalities specified will be returned.
+    /// The default only returns functional alleles.
+    pub functionalities: Option<HashSet<Functionality>>,
+    /// If partial genes (that miss their 5' and/or 3' end) should be returned, these are only available if the germlines were generated with partial genes
+    pub include_partial: bool,
     /// The way of handling alleles you want
     pub allele: AlleleSelection,
+    /// The allotypes of constant alleles you want, None allows all, otherwise only constant alleles with
+    /// any of the allotypes specified will be returned. Genes that are not constant (V/D/J) are always returned.
+    pub allotypes: Option<HashSet<Allotype>>,
 }
 
 impl Selection {
@@ -57,19 +66,63 @@ impl Selection {
         }
     }
 
+    /// Builder pattern method to add an isoform selection, will replace any previously set isoform selection
+    #[must_use]
+    pub fn isoform(self, isoforms: impl Into<HashSet<Isoform>>) -> Self {
+        Self {
+            isoforms: Some(isoforms.into()),
+            ..self
+        }
+    }
+
+    /// Builder pattern method to add a functionality selection, will replace any previously set functionality selection
+    #[must_use]
+    pub fn functionality(self, functionalities: impl Into<HashSet<Functionality>>) -> Self {
+        Self {
+            functionalities: Some(functionalities.into()),
+            ..self
+        }
+    }
+
+    /// Builder pattern method to set if partial genes should be returned
+    #[must_use]
+    pub fn include_partial(self, include_partial: bool) -> Self {
+        Self {
+            include_partial,
+            ..self
+        }
+    }
+
     /// Builder pattern method to add an allele selection, will replace any previously set allele selection
     #[must_use]
     pub fn allele(self, allele: AlleleSelection) -> Self {
         Self { allele, ..self }
     }
 
+    /// Builder pattern method to add an allotype selection, will replace any previously set allotype selection
+    #[must_use]
+    pub fn allotype(self, allotypes: impl Into<HashSet<Allotype>>) -> Self {
+        Self {
+            allotypes: Some(allotypes.into()),
+            ..self
+        }
+    }
+
     /// Get the selected alleles
     pub fn germlines(self) -> impl Iterator<Item = Allele<'static>> {
-        crate::all_germlines()
+        self.select_from(crate::all_germlines())
+    }
+
+    /// Get the selected alleles from the given germline sets
+    pub(crate) fn select_from<'a>(
+        self,
+        germlines: impl Iterator<Item = &'a Germlines> + 'a,
+    ) -> impl Iterator<Item = Allele<'a>> + 'a {
+        germlines
             .filter(move |g| {
-                self.species
-                    .as_ref()
-                    .map_or(true, |s| s.contains(&g.species))
+                self.species.as_ref().map_or(true, |s| {
+                    g.species.is_some_and(|species| s.contains(&species))
+                })
             })
             .flat_map(|g| g.into_iter().map(|c| (g.species, c.0, c.1)))
             .filter(move |(_, kind, _)| self.chains.as_ref().map_or(true, |k| k.contains(kind)))
@@ -77,9 +130,24 @@ impl Selection {
             .filter(move |(_, gene, _)| self.genes.as_ref().map_or(true, |s| s.contains(gene)))
             .flat_map(|(species, _, germlines)| germlines.iter().map(move |a| (species, a)))
             .flat_map(move |(species, germline)| {
-                germline
+                self.allele
+                    .select(germline, |seq| {
+                        self.isoforms
+                            .as_ref()
+                            .map_or(true, |s| seq.isoform.map_or(true, |i| s.contains(&i)))
+                            && self
+                                .functionalities
+                                .as_ref()
+                                .map_or(true, |s| s.contains(&seq.functionality))
+                            && (self.include_partial || !seq.truncation.is_partial())
+                            && self.allotypes.as_ref().map_or(true, |s| {
+                                !matches!(germline.name.gene, GeneType::C(_))
+                                    || seq.annotations.iter().any(|(a, _)| {
+                                        matches!(a, Annotation::Allotype(allotype) if s.contains(allotype))
+                                    })
+                            })
+                    })
                     .into_iter()
-                    .take(self.allele.take_num())
                     .map(move |(a, seq)| (species, &germline.name, *a, seq))
             })
             .map(Into::into)
@@ -90,9 +158,9 @@ impl Selection {
     pub fn par_germlines(self) -> impl ParallelIterator<Item = Allele<'static>> {
         crate::par_germlines()
             .filter(move |g| {
-                self.species
-                    .as_ref()
-                    .map_or(true, |s| s.contains(&g.species))
+                self.species.as_ref().map_or(true, |s| {
+                    g.species.is_some_and(|species| s.contains(&species))
+                })
             })
             .flat_map(|g| g.into_par_iter().map(|c| (g.species, c.0, c.1)))
             .filter(move |(_, kind, _)| self.chains.as_ref().map_or(true, |k| k.contains(kind)))
@@ -101,10 +169,25 @@ impl Selection {
             .flat_map(|(species, _, germlines)| {
                 germlines.into_par_iter().map(move |a| (species, a))
             })
-            .flat_map(move |(species, germline)| {
-                germline
-                    .into_par_iter()
-                    .take(self.allele.take_num())
+            .flat_map_iter(move |(species, germline)| {
+                self.allele
+                    .select(germline, |seq| {
+                        self.isoforms
+                            .as_ref()
+                            .map_or(true, |s| seq.isoform.map_or(true, |i| s.contains(&i)))
+                            && self
+                                .functionalities
+                                .as_ref()
+                                .map_or(true, |s| s.contains(&seq.functionality))
+                            && (self.include_partial || !seq.truncation.is_partial())
+                            && self.allotypes.as_ref().map_or(true, |s| {
+                                !matches!(germline.name.gene, GeneType::C(_))
+                                    || seq.annotations.iter().any(|(a, _)| {
+                                        matches!(a, Annotation::Allotype(allotype) if s.contains(allotype))
+                                    })
+                            })
+                    })
+                    .into_iter()
                     .map(move |(a, seq)| (species, &germline.name, *a, seq))
             })
             .map(Into::into)
@@ -112,13 +195,17 @@ impl Selection {
 }
 
 impl Default for Selection {
-    /// Get a default selection, which gives all kinds and genes but only returns the first allele
+    /// Get a default selection, which gives all kinds and genes but only returns the first functional allele, only secreted constant genes, and no partial genes
     fn default() -> Self {
         Self {
             species: None,
             chains: None,
             genes: None,
+            isoforms: Some([Isoform::Secreted].into()),
+            functionalities: Some([Functionality::Functional].into()),
+            include_partial: false,
             allele: AlleleSelection::First,
+            allotypes: None,
         }
     }
 }
@@ -129,15 +216,28 @@ pub enum AlleleSelection {
     /// Return all alleles
     All,
     /// Only return the first allele. It can have a number higher than 1 if the previous alleles are not functional.
+    /// For D genes the first allele is returned in all reading frames without a stop codon.
     First,
 }
 
 impl AlleleSelection {
-    const fn take_num(&self) -> usize {
-        match self {
-            Self::First => 1,
-            Self::All => usize::MAX,
+    /// Select the sequences from this germline that are accepted by the filter according to this allele selection
+    fn select<'a>(
+        &self,
+        germline: &'a Germline,
+        filter: impl Fn(&AnnotatedSequence) -> bool,
+    ) -> Vec<&'a (usize, AnnotatedSequence)> {
+        let mut selected = germline
+            .alleles
+            .iter()
+            .filter(|(_, seq)| filter(seq))
+            .collect::<Vec<_>>();
+        if *self == Self::First {
+            if let Some(first) = selected.first().map(|a| a.0) {
+                selected.retain(|a| a.0 == first);
+            }
         }
+        selected
     }
 }
 
@@ -145,21 +245,65 @@ impl AlleleSelection {
 #[non_exhaustive] // Do not let anyone build it themselves
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Allele<'a> {
-    /// The species where this gene originates from
-    pub species: Species,
+    /// The species where this gene originates from, `None` for alleles from [`crate::CustomGermlines`]
+    pub species: Option<Species>,
     /// The gene where this is the sequence for, eg `IGHV3-23`
     pub gene: std::borrow::Cow<'a, Gene>,
     /// The allele number, in IMGT this follows the name, eg `*01` is the allele in `IGHV3-23*01`
     pub allele: usize,
+    /// The reading frame (0, 1, or 2) this sequence is translated in, only set for D genes
+    pub reading_frame: Option<usize>,
+    /// The isoform (secreted or membrane bound) of this sequence, only set for C genes
+    pub isoform: Option<Isoform>,
+    /// The functionality of this allele as annotated by IMGT
+    pub functionality: Functionality,
     /// The actual sequence, the sequences present in the database are pure amino acids, no modifications are to be expected
     pub sequence: &'a LinearPeptide,
     /// The regions in the sequence, every region has an annotation and a length, all lengths together are the same length as the full sequence
     pub regions: &'a [(Region, usize)],
     /// Any additional annotations, every annotation has beside the kind it is also its location, as index in the sequence
     pub annotations: &'a [(Annotation, usize)],
+    /// The spliced coding DNA sequence this amino acid sequence was translated from
+    pub dna: &'a str,
+    /// The regions in the DNA sequence, every region has an annotation and a length in nucleotides, all lengths together are the same length as the full DNA sequence. A codon that spans a splice junction is counted for the region that contains its amino acid.
+    pub dna_regions: &'a [(Region, usize)],
+    /// The accessions in the source database that support this sequence
+    pub provenance: &'a Provenance,
+    /// The ends of the gene that are missing from this sequence, only set for partial genes
+    pub truncation: Truncation,
+    /// The leader (signal) peptide preceding the sequence, only set for V genes where it is annotated.
+    /// Use [`Allele::sequence_with_leader`] to get the full precursor sequence.
+    pub leader: Option<&'a LinearPeptide>,
+    /// Other distinct sequences reported for this allele
+    alternatives: &'a [AnnotatedSequence],
 }
 
 impl<'a> Allele<'a> {
+    fn from_sequence(
+        species: Option<Species>,
+        gene: std::borrow::Cow<'a, Gene>,
+        allele: usize,
+        seq: &'a AnnotatedSequence,
+    ) -> Self {
+        Self {
+            species,
+            gene,
+            allele,
+            reading_frame: seq.reading_frame,
+            isoform: seq.isoform,
+            functionality: seq.functionality,
+            sequence: &seq.sequence,
+            regions: &seq.regions,
+            annotations: &seq.annotations,
+            dna: &seq.dna,
+            dna_regions: &seq.dna_regions,
+            provenance: &seq.provenance,
+            truncation: seq.truncation,
+            leader: seq.leader.as_ref(),
+            alternatives: &seq.alternatives,
+        }
+    }
+
     /// Get the IMGT name for this allele
     pub fn name(&self) -> String {
         format!("{}*{:02}", self.gene, self.allele)
@@ -170,12 +314,121 @@ impl<'a> Allele<'a> {
         format!("{}*{:02}", self.gene.to_fancy_string(), self.allele)
     }
 
+    /// Get the other distinct sequences that are reported for this allele, sorted on the number of supporting accessions.
+    /// The returned alleles have the same name, use [`Allele::provenance`] to see the support for each sequence.
+    pub fn alternatives(&self) -> impl Iterator<Item = Allele<'a>> + '_ {
+        self.alternatives
+            .iter()
+            .map(|seq| Self::from_sequence(self.species, self.gene.clone(), self.allele, seq))
+    }
+
+    /// If multiple distinct complete sequences are reported for this allele
+    pub fn is_ambiguous(&self) -> bool {
+        self.alternatives
+            .iter()
+            .any(|seq| !seq.truncation.is_partial())
+    }
+
+    /// Get the sequence with the leader peptide prepended, or only the (mature) sequence if the leader
+    /// is not requested or not known. Note that the annotations are relative to the mature sequence, use
+    /// [`Allele::annotations_with_leader`] for annotations relative to this sequence.
+    pub fn sequence_with_leader(
+        &self,
+        include_leader: bool,
+    ) -> std::borrow::Cow<'a, LinearPeptide> {
+        self.leader.filter(|_| include_leader).map_or(
+            std::borrow::Cow::Borrowed(self.sequence),
+            |leader| {
+                let mut sequence: LinearPeptide = leader
+                    .sequence
+                    .iter()
+                    .chain(&self.sequence.sequence)
+                    .cloned()
+                    .collect();
+                sequence.c_term.clone_from(&self.sequence.c_term);
+                std::borrow::Cow::Owned(sequence)
+            },
+        )
+    }
+
+    /// Get the regions with [`Region::Leader`] as first region if the leader is requested and known,
+    /// these match [`Allele::sequence_with_leader`] with the same setting.
+    pub fn regions_with_leader(&self, include_leader: bool) -> Vec<(Region, usize)> {
+        self.leader
+            .filter(|_| include_leader)
+            .map(|leader| (Region::Leader, leader.sequence.len()))
+            .into_iter()
+            .chain(self.regions.iter().copied())
+            .collect()
+    }
+
+    /// Get the annotations with all positions (including the partners of intra chain disulfide bonds)
+    /// shifted by the length of the leader if the leader is requested and known, these match
+    /// [`Allele::sequence_with_leader`] with the same setting.
+    pub fn annotations_with_leader(&self, include_leader: bool) -> Vec<(Annotation, usize)> {
+        let shift = self
+            .leader
+            .filter(|_| include_leader)
+            .map_or(0, |leader| leader.sequence.len());
+        self.annotations
+            .iter()
+            .map(|(annotation, index)| match annotation {
+                Annotation::Disulfide(DisulfidePartner::Intra(partner)) => (
+                    Annotation::Disulfide(DisulfidePartner::Intra(partner + shift)),
+                    index + shift,
+                ),
+                annotation => (*annotation, index + shift),
+            })
+            .collect()
+    }
+
+    /// Get the allotypes of this allele, only set for human constant alleles. The defining residues are
+    /// annotated with [`Annotation::Allotype`].
+    pub fn allotypes(&self) -> impl Iterator<Item = Allotype> + 'a {
+        self.annotations
+            .iter()
+            .filter_map(|(annotation, _)| match annotation {
+                Annotation::Allotype(allotype) => Some(*allotype),
+                _ => None,
+            })
+            .unique()
+    }
+
+    /// If this is a partial gene, so missing its 5' and/or 3' end
+    pub const fn is_partial(&self) -> bool {
+        self.truncation.is_partial()
+    }
+
+    /// Check the consistency of this allele: the region lengths sum to the sequence length (and the same
+    /// for the DNA), the conserved residues (eg [`Annotation::Cysteine1`]) point at the right amino acid,
+    /// and the CDR and FR regions appear in order.
+    /// # Errors
+    /// If any of these checks fail, with a message for every violation prefixed by the allele name
+    pub fn validate(&self) -> Result<(), Vec<String>> {
+        let violations = crate::shared::validate(
+            self.sequence,
+            self.regions,
+            self.annotations,
+            self.dna,
+            self.dna_regions,
+        );
+        if violations.is_empty() {
+            Ok(())
+        } else {
+            let name = self.name();
+            Err(violations
+                .into_iter()
+                .map(|violation| format!("{name}: {violation}"))
+                .collect())
+        }
+    }
+
     /// Get the region for a specific index into the sequence, None if outside range,
     /// the additional bool indicates if this is the starting position for the region
     pub fn region(&self, index: usize) -> Option<(Region, bool)> {
         let mut left = index;
         let mut regions_index = 0;
-        let mut next = self.regions[regions_index];
+        let mut next = *self.regions.first()?;
         while left > next.1 {
             left -= next.1;
             regions_index += 1;
@@ -187,6 +440,19 @@ impl<'a> Allele<'a> {
         Some((next.0, left == 1))
     }
 
+    /// Get the region for a specific index into the DNA sequence, None if outside range,
+    /// the additional bool indicates if this is the starting position for the region
+    pub fn dna_region(&self, index: usize) -> Option<(Region, bool)> {
+        let mut left = index;
+        for region in self.dna_regions {
+            if left < region.1 {
+                return Some((region.0, left == 0));
+            }
+            left -= region.1;
+        }
+        None
+    }
+
     /// Get all annotations for this position
     pub fn annotations(&self, index: usize) -> impl Iterator<Item = Annotation> + 'a {
         self.annotations
@@ -196,29 +462,33 @@ impl<'a> Allele<'a> {
     }
 }
 
-impl<'a> From<(Species, &'a Gene, usize, &'a AnnotatedSequence)> for Allele<'a> {
-    fn from(value: (Species, &'a Gene, usize, &'a AnnotatedSequence)) -> Self {
-        Self {
-            species: value.0,
-            gene: std::borrow::Cow::Borrowed(value.1),
-            allele: value.2,
-            sequence: &value.3.sequence,
-            regions: &value.3.regions,
-            annotations: &value.3.annotations,
-        }
+impl<'a> From<(Option<Species>, &'a Gene, usize, &'a AnnotatedSequence)> for Allele<'a> {
+    fn from(value: (Option<Species>, &'a Gene, usize, &'a AnnotatedSequence)) -> Self {
+        Self::from_sequence(
+            value.0,
+            std::borrow::Cow::Borrowed(value.1),
+            value.2,
+            value.3,
+        )
     }
 }
 
 impl Germlines {
-    pub fn find(&self, species: Species, gene: Gene, allele: Option<usize>) -> Option<Allele<'_>> {
+    /// Get all sequences stored for the given allele, or for the first allele if none is given
+    pub fn find(&self, gene: Gene, allele: Option<usize>) -> Vec<Allele<'_>> {
         let chain = match gene.chain {
             ChainType::Heavy => &self.h,
             ChainType::LightKappa => &self.k,
             ChainType::LightLambda => &self.l,
             ChainType::Iota => &self.i,
+            ChainType::Alpha => &self.a,
+            ChainType::Beta => &self.b,
+            ChainType::Gamma => &self.g,
+            ChainType::Delta => &self.d,
         };
         let genes = match gene.gene {
             GeneType::V => &chain.variable,
+            GeneType::D => &chain.diversity,
             GeneType::J => &chain.joining,
             GeneType::C(_) => &chain.constant,
         };
@@ -226,19 +496,16 @@ impl Germlines {
             .binary_search_by(|g| g.name.cmp(&gene))
             .ok()
             .and_then(|g| {
-                let g = &genes[g];
-                allele.map_or(g.alleles.first(), |a| {
-                    g.alleles.iter().find(|(ga, _)| a == *ga)
-                })
+                let alleles = &genes[g].alleles;
+                let allele = allele.or_else(|| alleles.first().map(|a| a.0))?;
+                Some(alleles.iter().filter(move |(a, _)| *a == allele))
             })
-            .map(move |(a, seq)| Allele {
-                species,
-                gene: std::borrow::Cow::Owned(gene),
-                allele: *a,
-                sequence: &seq.sequence,
-                regions: &seq.regions,
-                annotations: &seq.annotations,
+            .into_iter()
+            .flatten()
+            .map(|(a, seq)| {
+                Allele::from_sequence(self.species, std::borrow::Cow::Owned(gene.clone()), *a, seq)
             })
+            .collect()
     }
 }
 
@@ -246,7 +513,10 @@ impl Germlines {
 #[allow(clippy::missing_panics_doc)]
 mod tests {
     use crate::Selection;
-    use crate::{ChainType, GeneType, Species};
+    use crate::{
+        AnnotatedSequence, Annotation, ChainType, CustomGermlines, DisulfidePartner, Functionality,
+        Gene, GeneType, Germline, Germlines, Isoform, Region, Species,
+    };
 
     #[test]
     fn try_first_human() {
@@ -257,4 +527,199 @@ mod tests {
         let first = selection.germlines().next().unwrap();
         assert_eq!(first.name(), "IGHV1-2*01");
     }
+
+    /// Build a set of human germlines from the given alleles
+    fn germlines(alleles: Vec<(&str, AnnotatedSequence)>) -> Germlines {
+        let mut germlines = Germlines::new(Species::HomoSapiens);
+        for (name, sequence) in alleles {
+            let (name, allele) = Gene::from_imgt_name_with_allele(name).unwrap();
+            germlines.insert(Germline {
+                name,
+                alleles: vec![(allele, sequence)],
+            });
+        }
+        germlines
+    }
+
+    /// Build an annotated sequence without regions or annotations
+    fn sequence(sequence: &str) -> AnnotatedSequence {
+        let sequence = sequence
+            .bytes()
+            .map(|b| rustyms::AminoAcid::try_from(b).unwrap())
+            .collect::<Vec<_>>();
+        AnnotatedSequence::new(sequence.into(), Vec::new(), Vec::new())
+    }
+
+    #[test]
+    fn find_all_entries() {
+        let frame = |frame, seq| AnnotatedSequence {
+            reading_frame: Some(frame),
+            ..sequence(seq)
+        };
+        let isoform = |isoform, seq| AnnotatedSequence {
+            isoform: Some(isoform),
+            ..sequence(seq)
+        };
+        let germlines = germlines(vec![
+            ("IGHD1-1*01", frame(0, "GTTG")),
+            ("IGHD1-1*01", frame(1, "VQLE")),
+            ("IGHD1-1*02", frame(0, "GTTA")),
+            ("IGHG1*01", isoform(Isoform::Secreted, "ASTK")),
+            ("IGHG1*01", isoform(Isoform::Membrane, "ASTKELQL")),
+        ]);
+        let find = |name, allele| germlines.find(Gene::from_imgt_name(name).unwrap(), allele);
+        let d = find("IGHD1-1", None);
+        assert_eq!(
+            d.iter()
+                .map(|a| (a.allele, a.reading_frame, a.sequence.to_string()))
+                .collect::<Vec<_>>(),
+            [
+                (1, Some(0), "GTTG".to_string()),
+                (1, Some(1), "VQLE".to_string())
+            ]
+        );
+        assert_eq!(find("IGHD1-1", Some(2)).len(), 1);
+        assert!(find("IGHD1-1", Some(3)).is_empty());
+        assert!(find("IGHD1-2", None).is_empty());
+        assert_eq!(
+            find("IGHG1", Some(1))
+                .iter()
+                .map(|a| a.isoform)
+                .collect::<Vec<_>>(),
+            [Some(Isoform::Secreted), Some(Isoform::Membrane)]
+        );
+    }
+
+    #[test]
+    fn alternatives() {
+        let supported = |seq, accessions: &[&str]| {
+            let mut sequence = sequence(seq);
+            sequence.provenance.accessions = accessions.iter().map(ToString::to_string).collect();
+            sequence
+        };
+        let germlines = germlines(vec![
+            ("IGHV1-2*01", supported("QVQV", &["A1"])),
+            ("IGHV1-2*01", supported("QVQL", &["A2", "A3", "A4"])),
+            ("IGHV1-3*01", supported("EVQL", &["A5"])),
+        ]);
+        let find = |name| {
+            germlines
+                .find(Gene::from_imgt_name(name).unwrap(), Some(1))
+                .remove(0)
+        };
+        let allele = find("IGHV1-2");
+        assert_eq!(allele.sequence.to_string(), "QVQL");
+        assert_eq!(allele.provenance.sequence_support(), 3);
+        assert_eq!(
+            allele
+                .alternatives()
+                .map(|a| (a.sequence.to_string(), a.provenance.sequence_support()))
+                .collect::<Vec<_>>(),
+            [("QVQV".to_string(), 1)]
+        );
+        assert!(allele.is_ambiguous());
+        assert!(!find("IGHV1-3").is_ambiguous());
+    }
+
+    #[test]
+    fn functionality() {
+        let functionality = |functionality, seq| AnnotatedSequence {
+            functionality,
+            ..sequence(seq)
+        };
+        let germlines = germlines(vec![
+            (
+                "IGHV1-2*01",
+                functionality(Functionality::Functional, "QVQL"),
+            ),
+            (
+                "IGHV1-3*01",
+                functionality(Functionality::OpenReadingFrame, "QVQL"),
+            ),
+            (
+                "IGHV1-4*01",
+                functionality(Functionality::InFramePseudogene, "QVQL"),
+            ),
+            (
+                "IGHV1-5*01",
+                functionality(Functionality::Pseudogene, "QVQL"),
+            ),
+        ]);
+        let names = |selection: Selection| {
+            selection
+                .select_from(std::iter::once(&germlines))
+                .map(|a| a.name())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(names(Selection::default()), ["IGHV1-2*01"]);
+        assert_eq!(
+            names(
+                Selection::default()
+                    .functionality([Functionality::Functional, Functionality::OpenReadingFrame])
+            ),
+            ["IGHV1-2*01", "IGHV1-3*01"]
+        );
+        assert_eq!(
+            names(Selection::default().functionality([Functionality::Pseudogene])),
+            ["IGHV1-5*01"]
+        );
+    }
+
+    #[test]
+    fn leader() {
+        let custom = CustomGermlines::from_fasta(
+            "Test",
+            ">IGHV1-2*01 Leader:3 FR1:5\nMDWQVQLV\n>IGHV1-3*01 FR1:5\nEVQLL\n".as_bytes(),
+        )
+        .unwrap();
+        let with = custom
+            .get(Gene::from_imgt_name("IGHV1-2").unwrap(), Some(1))
+            .remove(0);
+        assert_eq!(with.sequence_with_leader(true).to_string(), "MDWQVQLV");
+        assert_eq!(with.sequence_with_leader(false).to_string(), "QVQLV");
+        assert_eq!(
+            with.regions_with_leader(true),
+            [(Region::Leader, 3), (Region::FR1, 5)]
+        );
+        assert_eq!(with.regions_with_leader(false), [(Region::FR1, 5)]);
+
+        let without = custom
+            .get(Gene::from_imgt_name("IGHV1-3").unwrap(), Some(1))
+            .remove(0);
+        assert_eq!(without.sequence_with_leader(true).to_string(), "EVQLL");
+        assert_eq!(without.regions_with_leader(true), [(Region::FR1, 5)]);
+
+        let annotations = vec![
+            (Annotation::Cysteine1, 1),
+            (Annotation::Disulfide(DisulfidePartner::Intra(4)), 1),
+            (Annotation::Disulfide(DisulfidePartner::Intra(1)), 4),
+        ];
+        let annotated = germlines(vec![(
+            "IGHV1-2*01",
+            AnnotatedSequence {
+                leader: Some(sequence("MDW").sequence),
+                ..AnnotatedSequence::new(
+                    sequence("QCQLC").sequence,
+                    vec![(Region::FR1, 5)],
+                    annotations.clone(),
+                )
+            },
+        )]);
+        let with = annotated
+            .find(Gene::from_imgt_name("IGHV1-2").unwrap(), Some(1))
+            .remove(0);
+        assert_eq!(
+            with.annotations_with_leader(true),
+            [
+                (Annotation::Cysteine1, 4),
+                (Annotation::Disulfide(DisulfidePartner::Intra(7)), 4),
+                (Annotation::Disulfide(DisulfidePartner::Intra(4)), 7),
+            ]
+        );
+        assert_eq!(with.annotations_with_leader(false), annotations);
+        assert_eq!(
+            without.annotations_with_leader(true),
+            without.annotations.to_vec()
+        );
+    }
 }
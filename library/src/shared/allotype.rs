@@ -0,0 +1,248 @@
+#![allow(dead_code)]
+use rustyms::AminoAcid;
+use serde::{Deserialize, Serialize};
+use std::{fmt::Display, str::FromStr};
+
+use super::{Annotation, Gene, Species};
+
+/// A serological allotype of a human constant gene, named following the WHO nomenclature.
+///
+/// The allotypes are defined by one or more residues, these are annotated with [`Annotation::Allotype`] on the constant alleles.
+#[derive(Debug, Serialize, Deserialize, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Allotype {
+    /// G1m1 (a), Asp356 and Leu358 in the CH3 of IGHG1
+    G1m1,
+    /// nG1m1, Glu356 and Met358 in the CH3 of IGHG1
+    NG1m1,
+    /// G1m2 (x), Gly431 in the CH3 of IGHG1
+    G1m2,
+    /// G1m3 (f), Arg214 in the CH1 of IGHG1
+    G1m3,
+    /// G1m17 (z), Lys214 in the CH1 of IGHG1
+    G1m17,
+    /// G2m23 (n), Met282 in the CH2 of IGHG2
+    G2m23,
+    /// Km1, Val153 and Leu191 in IGKC
+    Km1,
+    /// Km1,2, Ala153 and Leu191 in IGKC
+    Km1And2,
+    /// Km3, Ala153 and Val191 in IGKC
+    Km3,
+}
+
+impl Allotype {
+    /// All known allotypes
+    pub const ALL: [Self; 9] = [
+        Self::G1m1,
+        Self::NG1m1,
+        Self::G1m2,
+        Self::G1m3,
+        Self::G1m17,
+        Self::G2m23,
+        Self::Km1,
+        Self::Km1And2,
+        Self::Km3,
+    ];
+
+    /// The alphabetic name for this allotype, eg `G1m(f)` for G1m3, if it has one
+    pub const fn alphabetic_name(self) -> Option<&'static str> {
+        match self {
+            Self::G1m1 => Some("G1m(a)"),
+            Self::G1m2 => Some("G1m(x)"),
+            Self::G1m3 => Some("G1m(f)"),
+            Self::G1m17 => Some("G1m(z)"),
+            Self::G2m23 => Some("G2m(n)"),
+            Self::NG1m1 | Self::Km1 | Self::Km1And2 | Self::Km3 => None,
+        }
+    }
+
+    /// The gene this allotype is defined on, and the motifs that define it. Every motif is given with
+    /// the offsets of the defining residues in the motif, all motifs have to be present.
+    const fn definition(self) -> (&'static str, &'static [(&'static str, &'static [usize])]) {
+        match self {
+            Self::G1m1 => ("IGHG1", &[("RDELTKNQ", &[1, 3])]),
+            Self::NG1m1 => ("IGHG1", &[("REEMTKNQ", &[1, 3])]),
+            Self::G1m2 => ("IGHG1", &[("HEGLHNHY", &[2])]),
+            Self::G1m3 => ("IGHG1", &[("KVDKRVEP", &[4])]),
+            Self::G1m17 => ("IGHG1", &[("KVDKKVEP", &[4])]),
+            Self::G2m23 => ("IGHG2", &[("VDGMEVHN", &[3])]),
+            Self::Km1 => ("IGKC", &[("DNVLQSGN", &[2]), ("HKLYACEV", &[2])]),
+            Self::Km1And2 => ("IGKC", &[("DNALQSGN", &[2]), ("HKLYACEV", &[2])]),
+            Self::Km3 => ("IGKC", &[("DNALQSGN", &[2]), ("HKVYACEV", &[2])]),
+        }
+    }
+
+    /// Find all allotypes of a constant gene, returned as annotations on the defining residues.
+    /// Allotypes are only defined for human genes.
+    pub(crate) fn annotate(
+        species: Species,
+        gene: &Gene,
+        sequence: &[AminoAcid],
+    ) -> Vec<(Annotation, usize)> {
+        if species != Species::HomoSapiens {
+            return Vec::new();
+        }
+        let name = gene.to_string();
+        let mut annotations = Vec::new();
+        for allotype in Self::ALL {
+            let (allotype_gene, motifs) = allotype.definition();
+            if name != allotype_gene {
+                continue;
+            }
+            let found = motifs
+                .iter()
+                .map(|(motif, offsets)| {
+                    sequence
+                        .windows(motif.len())
+                        .position(|window| {
+                            window
+                                .iter()
+                                .zip(motif.chars())
+                                .all(|(aa, expected)| aa.char() == expected)
+                        })
+                        .map(|start| offsets.iter().map(move |offset| start + offset))
+                })
+                .collect::<Option<Vec<_>>>();
+            if let Some(found) = found {
+                annotations.extend(
+                    found
+                        .into_iter()
+                        .flatten()
+                        .map(|index| (Annotation::Allotype(allotype), index)),
+                );
+            }
+        }
+        annotations
+    }
+}
+
+impl Display for Allotype {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                Self::G1m1 => "G1m1",
+                Self::NG1m1 => "nG1m1",
+                Self::G1m2 => "G1m2",
+                Self::G1m3 => "G1m3",
+                Self::G1m17 => "G1m17",
+                Self::G2m23 => "G2m23",
+                Self::Km1 => "Km1",
+                Self::Km1And2 => "Km1,2",
+                Self::Km3 => "Km3",
+            }
+        )
+    }
+}
+
+impl FromStr for Allotype {
+    type Err = ();
+    /// Parse the numeric (eg `G1m3`) or alphabetic (eg `G1m(f)`) name of an allotype
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|allotype| allotype.to_string() == s || allotype.alphabetic_name() == Some(s))
+            .ok_or(())
+    }
+}
+
+#[allow(clippy::missing_panics_doc)]
+#[test]
+fn allotypes() {
+    let annotate = |species, gene: &str, sequence: &str| {
+        Allotype::annotate(
+            species,
+            &Gene::from_imgt_name(gene).unwrap(),
+            &sequence
+                .bytes()
+                .map(|b| AminoAcid::try_from(b).unwrap())
+                .collect::<Vec<_>>(),
+        )
+    };
+    let position =
+        |sequence: &str, motif: &str, offset: usize| sequence.find(motif).unwrap() + offset;
+
+    // IGHG1*01 is G1m17,1 (z,a)
+    let ighg1_01 = "ASTKGPSVFPLAPSSKSTSGGTAALGCLVKDYFPEPVTVSWNSGALTSGVHTFPAVLQSSGLYSLSSVVTVPSSSLGTQTYICNVNHKPSNTKVDKKVEPKSCDKTHTCPPCPGQPREPQVYTLPPSRDELTKNQVSLTCLVKGFYPSDIAVEWESNGQPENNYKTTPPVLDSDGSFFLYSKLTVDKSRWQQGNVFSCSVMHEALHNHYTQKSLSLSPGK";
+    assert_eq!(
+        annotate(Species::HomoSapiens, "IGHG1", ighg1_01),
+        [
+            (
+                Annotation::Allotype(Allotype::G1m1),
+                position(ighg1_01, "RDELTKNQ", 1)
+            ),
+            (
+                Annotation::Allotype(Allotype::G1m1),
+                position(ighg1_01, "RDELTKNQ", 3)
+            ),
+            (
+                Annotation::Allotype(Allotype::G1m17),
+                position(ighg1_01, "KVDKKVEP", 4)
+            ),
+        ]
+    );
+    // IGHG1*03 is G1m3 (f) and nG1m1
+    let ighg1_03 = ighg1_01
+        .replace("KVDKKVEP", "KVDKRVEP")
+        .replace("RDELTKNQ", "REEMTKNQ");
+    assert_eq!(
+        annotate(Species::HomoSapiens, "IGHG1", &ighg1_03),
+        [
+            (
+                Annotation::Allotype(Allotype::NG1m1),
+                position(&ighg1_03, "REEMTKNQ", 1)
+            ),
+            (
+                Annotation::Allotype(Allotype::NG1m1),
+                position(&ighg1_03, "REEMTKNQ", 3)
+            ),
+            (
+                Annotation::Allotype(Allotype::G1m3),
+                position(&ighg1_03, "KVDKRVEP", 4)
+            ),
+        ]
+    );
+    // The same residues on another gene or species are not an allotype
+    assert!(annotate(Species::HomoSapiens, "IGHG2", ighg1_01).is_empty());
+    assert!(annotate(Species::MusMusculus, "IGHG1", ighg1_01).is_empty());
+
+    // IGKC*01 is Km3, both motifs are needed
+    let igkc = "RTVAAPSVFIFPPSDEQLKSGTASVVCLLNNFYPREAKVQWKVDNALQSGNSQESVTEQDSKDSTYSLSSTLTLSKADYEKHKVYACEVTHQGLSSPVTKSFNRGEC";
+    assert_eq!(
+        annotate(Species::HomoSapiens, "IGKC", igkc),
+        [
+            (
+                Annotation::Allotype(Allotype::Km3),
+                position(igkc, "DNALQSGN", 2)
+            ),
+            (
+                Annotation::Allotype(Allotype::Km3),
+                position(igkc, "HKVYACEV", 2)
+            ),
+        ]
+    );
+    let igkc_02 = igkc.replace("HKVYACEV", "HKLYACEV");
+    assert_eq!(
+        annotate(Species::HomoSapiens, "IGKC", &igkc_02),
+        [
+            (
+                Annotation::Allotype(Allotype::Km1And2),
+                position(&igkc_02, "DNALQSGN", 2)
+            ),
+            (
+                Annotation::Allotype(Allotype::Km1And2),
+                position(&igkc_02, "HKLYACEV", 2)
+            ),
+        ]
+    );
+    assert!(annotate(
+        Species::HomoSapiens,
+        "IGKC",
+        &igkc.replace("DNALQSGN", "DNAIQSGN")
+    )
+    .is_empty());
+
+    assert_eq!("G1m(f)".parse(), Ok(Allotype::G1m3));
+    assert_eq!("Km1,2".parse(), Ok(Allotype::Km1And2));
+}
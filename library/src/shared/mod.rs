@@ -1,4 +1,6 @@
+mod allotype;
 mod regions;
 mod species;
+pub use allotype::*;
 pub use regions::*;
 pub use species::*;
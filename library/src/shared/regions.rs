@@ -1,28 +1,46 @@
 #![allow(dead_code)]
-use rustyms::LinearPeptide;
+use itertools::Itertools;
+use rustyms::{AminoAcid, LinearPeptide};
 use serde::{Deserialize, Serialize};
 use std::{fmt::Display, str::FromStr};
 
-use super::species::Species;
+use super::{allotype::Allotype, species::Species};
 
 /// A selection of germlines from a single species. Use the [`Self::get`] method to retrieve the sequences you are interested in.
 #[derive(Serialize, Deserialize, Debug)]
 pub(crate) struct Germlines {
-    pub(crate) species: Species,
+    /// The species of this set, `None` for custom germline sets which are identified by their label
+    pub(crate) species: Option<Species>,
     pub(crate) h: Chain,
     pub(crate) k: Chain,
     pub(crate) l: Chain,
     pub(crate) i: Chain,
+    pub(crate) a: Chain,
+    pub(crate) b: Chain,
+    pub(crate) g: Chain,
+    pub(crate) d: Chain,
 }
 
 impl Germlines {
     pub(crate) fn new(species: Species) -> Self {
         Self {
-            species,
+            species: Some(species),
+            ..Self::custom()
+        }
+    }
+
+    /// An empty germline set without a species
+    pub(crate) fn custom() -> Self {
+        Self {
+            species: None,
             h: Chain::default(),
             k: Chain::default(),
             l: Chain::default(),
             i: Chain::default(),
+            a: Chain::default(),
+            b: Chain::default(),
+            g: Chain::default(),
+            d: Chain::default(),
         }
     }
 
@@ -32,12 +50,16 @@ impl Germlines {
             ChainType::LightKappa => self.k.insert(germline),
             ChainType::LightLambda => self.l.insert(germline),
             ChainType::Iota => self.i.insert(germline),
+            ChainType::Alpha => self.a.insert(germline),
+            ChainType::Beta => self.b.insert(germline),
+            ChainType::Gamma => self.g.insert(germline),
+            ChainType::Delta => self.d.insert(germline),
         };
     }
 }
 
 impl<'a> IntoIterator for &'a Germlines {
-    type IntoIter = std::array::IntoIter<(ChainType, &'a Chain), 4>;
+    type IntoIter = std::array::IntoIter<(ChainType, &'a Chain), 8>;
     type Item = (ChainType, &'a Chain);
 
     fn into_iter(self) -> Self::IntoIter {
@@ -46,6 +68,10 @@ impl<'a> IntoIterator for &'a Germlines {
             (ChainType::LightKappa, &self.k),
             (ChainType::LightLambda, &self.l),
             (ChainType::Iota, &self.i),
+            (ChainType::Alpha, &self.a),
+            (ChainType::Beta, &self.b),
+            (ChainType::Gamma, &self.g),
+            (ChainType::Delta, &self.d),
         ]
         .into_iter()
     }
@@ -55,7 +81,7 @@ impl<'a> IntoIterator for &'a Germlines {
 use rayon::prelude::*;
 #[cfg(feature = "rayon")]
 impl<'a> IntoParallelIterator for &'a Germlines {
-    type Iter = rayon::array::IntoIter<(ChainType, &'a Chain), 4>;
+    type Iter = rayon::array::IntoIter<(ChainType, &'a Chain), 8>;
     type Item = (ChainType, &'a Chain);
 
     fn into_par_iter(self) -> Self::Iter {
@@ -64,6 +90,10 @@ impl<'a> IntoParallelIterator for &'a Germlines {
             (ChainType::LightKappa, &self.k),
             (ChainType::LightLambda, &self.l),
             (ChainType::Iota, &self.i),
+            (ChainType::Alpha, &self.a),
+            (ChainType::Beta, &self.b),
+            (ChainType::Gamma, &self.g),
+            (ChainType::Delta, &self.d),
         ]
         .into_par_iter()
     }
@@ -72,59 +102,37 @@ impl<'a> IntoParallelIterator for &'a Germlines {
 #[derive(Serialize, Deserialize, Default, Debug)]
 pub(crate) struct Chain {
     pub variable: Vec<Germline>,
+    pub diversity: Vec<Germline>,
     pub joining: Vec<Germline>,
     pub constant: Vec<Germline>,
 }
 
 impl Chain {
+    /// Insert a germline with a single allele, if this allele is already present the sequences are merged
+    /// with the best supported sequence as main sequence and all others as alternatives.
     /// # Panics
-    /// It panics when it inserts an allele it has already placed (has to be filtered and ranked before)
+    /// It panics when the germline does not contain an allele
     pub(crate) fn insert(&mut self, mut germline: Germline) {
         let db = match &germline.name.gene {
             GeneType::V => &mut self.variable,
+            GeneType::D => &mut self.diversity,
             GeneType::J => &mut self.joining,
             GeneType::C(_) => &mut self.constant,
         };
 
         match db.binary_search_by_key(&germline.name, |g| g.name.clone()) {
-            // If there are multiple copies of the same region keep the one with the most annotations + regions
             Ok(index) => {
-                match db[index]
-                    .alleles
-                    .binary_search_by_key(&germline.alleles[0].0, |a| a.0)
-                {
-                    Ok(_allele_index) => {
-                        // if germline.alleles[0].1.sequence
-                        //     == db[index].alleles[allele_index].1.sequence
-                        // {
-                        //     db[index].alleles[allele_index].2 = format!(
-                        //         "{}|{}",
-                        //         db[index].alleles[allele_index].2, germline.alleles[0].2
-                        //     );
-                        // } else {
-                        //     let mut found = false;
-                        //     for existing in &mut db[index].duplicates {
-                        //         if existing.1.sequence == germline.alleles[0].1.sequence {
-                        //             existing.2 =
-                        //                 format!("{}|{}", existing.2, germline.alleles[0].2);
-                        //             found = true;
-                        //         }
-                        //     }
-                        //     if !found {
-                        //         db[index].duplicates.push(germline.alleles.pop().unwrap())
-                        //     }
-                        // }
-                        // if germline.alleles[0].1.conserved.len()
-                        //     + germline.alleles[0].1.regions.len()
-                        //     > db[index].alleles[allele_index].1.conserved.len()
-                        //         + db[index].alleles[allele_index].1.regions.len()
-                        // {
-                        //     db[index].alleles[allele_index] = germline.alleles.pop().unwrap()
-                        // }
-                        panic!(
-                            "Not allowed to have multiple sequences for one allele in a germline"
-                        )
-                    }
+                match db[index].alleles.binary_search_by_key(
+                    &(
+                        germline.alleles[0].0,
+                        germline.alleles[0].1.reading_frame,
+                        germline.alleles[0].1.isoform,
+                    ),
+                    |a| (a.0, a.1.reading_frame, a.1.isoform),
+                ) {
+                    Ok(allele_index) => db[index].alleles[allele_index]
+                        .1
+                        .merge(germline.alleles.pop().unwrap().1),
                     Err(allele_index) => db[index]
                         .alleles
                         .insert(allele_index, germline.alleles.pop().unwrap()),
@@ -136,24 +144,37 @@ impl Chain {
 
     pub(crate) fn doc_row(&self) -> String {
         format!(
-            "|{}/{}|{}/{}|{}/{}|",
+            "|{}/{}|{}/{}|{}/{}|{}/{}|",
             self.variable.len(),
             self.variable.iter().map(|g| g.alleles.len()).sum::<usize>(),
+            self.diversity.len(),
+            self.diversity
+                .iter()
+                .map(|g| g.alleles.iter().map(|a| a.0).dedup().count())
+                .sum::<usize>(),
             self.joining.len(),
             self.joining.iter().map(|g| g.alleles.len()).sum::<usize>(),
             self.constant.len(),
-            self.constant.iter().map(|g| g.alleles.len()).sum::<usize>(),
+            self.constant
+                .iter()
+                .map(|g| g
+                    .alleles
+                    .iter()
+                    .filter(|a| a.1.isoform != Some(Isoform::Membrane))
+                    .count())
+                .sum::<usize>(),
         )
     }
 }
 
 impl<'a> IntoIterator for &'a Chain {
-    type IntoIter = std::array::IntoIter<(GeneType, &'a [Germline]), 3>;
+    type IntoIter = std::array::IntoIter<(GeneType, &'a [Germline]), 4>;
     type Item = (GeneType, &'a [Germline]);
 
     fn into_iter(self) -> Self::IntoIter {
         [
             (GeneType::V, self.variable.as_slice()),
+            (GeneType::D, self.diversity.as_slice()),
             (GeneType::J, self.joining.as_slice()),
             (GeneType::C(None), self.constant.as_slice()),
         ]
@@ -163,12 +184,13 @@ impl<'a> IntoIterator for &'a Chain {
 
 #[cfg(feature = "rayon")]
 impl<'a> IntoParallelIterator for &'a Chain {
-    type Iter = rayon::array::IntoIter<(GeneType, &'a [Germline]), 3>;
+    type Iter = rayon::array::IntoIter<(GeneType, &'a [Germline]), 4>;
     type Item = (GeneType, &'a [Germline]);
 
     fn into_par_iter(self) -> Self::Iter {
         [
             (GeneType::V, self.variable.as_slice()),
+            (GeneType::D, self.diversity.as_slice()),
             (GeneType::J, self.joining.as_slice()),
             (GeneType::C(None), self.constant.as_slice()),
         ]
@@ -201,13 +223,31 @@ impl<'a> IntoParallelIterator for &'a Germline {
     }
 }
 
-#[derive(Clone, Serialize, Deserialize, Debug)]
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq, Eq)]
 pub(crate) struct AnnotatedSequence {
     pub sequence: LinearPeptide,
     /// The different regions in the sequence, defined by their name and length
     pub regions: Vec<(Region, usize)>,
     /// 0 based locations of single amino acid annotations, overlapping with the regions defined above
     pub annotations: Vec<(Annotation, usize)>,
+    /// The reading frame (0, 1, or 2) used to translate this sequence, only used for D genes which are stored in all three frames
+    pub reading_frame: Option<usize>,
+    /// The isoform of this sequence, only used for C genes which can be stored in both secreted and membrane bound form
+    pub isoform: Option<Isoform>,
+    /// The functionality of this allele as annotated by IMGT
+    pub functionality: Functionality,
+    /// The spliced coding DNA of this sequence, as lowercase nucleotides
+    pub dna: String,
+    /// The regions in the DNA sequence, defined by their name and length in nucleotides, a codon spanning a splice junction belongs to the region of its amino acid
+    pub dna_regions: Vec<(Region, usize)>,
+    /// The accessions that support this sequence
+    pub provenance: Provenance,
+    /// Which ends of this sequence are missing, only set for partial genes
+    pub truncation: Truncation,
+    /// Other distinct sequences reported for the same allele, sorted on the number of supporting accessions
+    pub alternatives: Vec<Self>,
+    /// The leader (signal) peptide preceding the mature sequence, only set for V genes where it is annotated
+    pub leader: Option<LinearPeptide>,
 }
 
 impl AnnotatedSequence {
@@ -221,16 +261,129 @@ impl AnnotatedSequence {
             sequence,
             regions,
             annotations: conserved,
+            reading_frame: None,
+            isoform: None,
+            functionality: Functionality::Functional,
+            dna: String::new(),
+            dna_regions: Vec::new(),
+            provenance: Provenance::default(),
+            truncation: Truncation::default(),
+            alternatives: Vec::new(),
+            leader: None,
+        }
+    }
+
+    /// Merge another sequence for the same allele into this one. The sequence with the most supporting
+    /// accessions is kept as main sequence, all others are kept as alternatives.
+    pub fn merge(&mut self, mut other: Self) {
+        let mut alternatives = std::mem::take(&mut self.alternatives);
+        alternatives.append(&mut other.alternatives);
+        if other.provenance.sequence_support() > self.provenance.sequence_support() {
+            std::mem::swap(self, &mut other);
         }
+        alternatives.push(other);
+        alternatives.sort_by_key(|a| std::cmp::Reverse(a.provenance.sequence_support()));
+        self.alternatives = alternatives;
     }
 }
 
+/// Check the invariants of an annotated sequence, every violation is returned as a separate message.
+/// * The region lengths sum to the length of the sequence if regions are annotated (and the same for the DNA if present)
+/// * The conserved residue annotations point at the right amino acid, and intra chain disulfide bonds are paired
+/// * The CDR and FR regions appear in order
+pub(crate) fn validate(
+    sequence: &LinearPeptide,
+    regions: &[(Region, usize)],
+    annotations: &[(Annotation, usize)],
+    dna: &str,
+    dna_regions: &[(Region, usize)],
+) -> Vec<String> {
+    let mut violations = Vec::new();
+    let length = regions.iter().map(|r| r.1).sum::<usize>();
+    if !regions.is_empty() && length != sequence.sequence.len() {
+        violations.push(format!(
+            "the regions sum to {length} residues but the sequence has {} residues",
+            sequence.sequence.len()
+        ));
+    }
+    if !dna.is_empty() {
+        let length = dna_regions.iter().map(|r| r.1).sum::<usize>();
+        if length != dna.len() {
+            violations.push(format!(
+                "the DNA regions sum to {length} nucleotides but the DNA has {} nucleotides",
+                dna.len()
+            ));
+        }
+    }
+    for (annotation, index) in annotations {
+        // Allotypes are defined by differing residues, so these can only be checked to be in the sequence
+        let expected: &[AminoAcid] = match annotation {
+            Annotation::Cysteine1 | Annotation::Cysteine2 | Annotation::Disulfide(_) => {
+                &[AminoAcid::C]
+            }
+            Annotation::Tryptophan => &[AminoAcid::W],
+            Annotation::Phenylalanine => &[AminoAcid::F],
+            Annotation::Glycine => &[AminoAcid::G],
+            Annotation::NGlycan | Annotation::NGlycanAtypical | Annotation::NGlycanOccupied => {
+                &[AminoAcid::N]
+            }
+            Annotation::OGlycan => &[AminoAcid::S, AminoAcid::T],
+            Annotation::Allotype(_) => &[],
+        };
+        match sequence.sequence.get(*index) {
+            None => violations.push(format!(
+                "{annotation} at {index} is outside of the sequence ({} residues)",
+                sequence.sequence.len()
+            )),
+            Some(element) if !expected.is_empty() && !expected.contains(&element.aminoacid) => {
+                violations.push(format!(
+                    "{annotation} at {index} points at {} instead of {}",
+                    element.aminoacid.char(),
+                    expected.iter().map(AminoAcid::char).collect::<String>()
+                ));
+            }
+            Some(_) => (),
+        }
+        if let Annotation::Disulfide(DisulfidePartner::Intra(partner)) = annotation {
+            if !annotations.contains(&(
+                Annotation::Disulfide(DisulfidePartner::Intra(*index)),
+                *partner,
+            )) {
+                violations.push(format!(
+                    "{annotation} at {index} is not paired with a cysteine at {partner}"
+                ));
+            }
+        }
+    }
+    let order = [
+        Region::FR1,
+        Region::CDR1,
+        Region::FR2,
+        Region::CDR2,
+        Region::FR3,
+        Region::CDR3,
+        Region::FR4,
+    ];
+    let mut last: Option<(usize, Region)> = None;
+    for (region, _) in regions {
+        if let Some(position) = order.iter().position(|r| r == region) {
+            if let Some((last_position, last_region)) = last {
+                if position <= last_position {
+                    violations.push(format!("{region} follows {last_region}"));
+                }
+            }
+            last = Some((position, *region));
+        }
+    }
+    violations
+}
+
 /// A germline gene name, broken up in its constituent parts.
 #[derive(Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord, Clone, Debug)]
 pub struct Gene {
     /// The chain of this gene (heavy/kappa etc)
     pub chain: ChainType,
-    /// The kind of gene (V/J/C)
+    /// The kind of gene (V/D/J/C)
     pub gene: GeneType,
     /// If present the additional number _IGHV_ **(I)**
     pub number: Option<usize>,
@@ -248,7 +401,8 @@ impl Display for Gene {
 
         write!(
             f,
-            "IG{}{}{}{}",
+            "{}{}{}{}{}",
+            self.chain.prefix(),
             self.chain,
             self.gene,
             self.number
@@ -264,7 +418,7 @@ impl Display for Gene {
         let mut first = true;
         let mut last_str = false;
         for element in &self.family {
-            if !first && !last_str {
+            if !first && !last_str && !element.1.starts_with('/') {
                 write!(f, "-")?;
             }
             write!(
@@ -350,13 +504,26 @@ impl Gene {
             }
         }
 
-        if s.starts_with("IG") {
-            let chain = s[2..3]
+        if s.starts_with("IG") || s.starts_with("TR") {
+            let chain: ChainType = s[2..3]
                 .parse()
                 .map_err(|()| format!("Invalid chain: `{}`", &s[2..3]))?;
-            let gene = s[3..4]
+            if chain.prefix() != &s[0..2] {
+                return Err(format!(
+                    "Invalid chain for locus `{}`: `{}`",
+                    &s[0..2],
+                    &s[2..3]
+                ));
+            }
+            let mut gene = s[3..4]
                 .parse()
                 .map_err(|()| format!("Invalid gene: `{}`", &s[3..4]))?;
+            // `IGHD` is the constant delta gene, while `IGHD1-1` is a diversity gene (and TR has no constant delta)
+            if gene == GeneType::C(Some(Constant::D))
+                && (chain.is_t_cell_receptor() || !(s.len() == 4 || s[4..].starts_with('*')))
+            {
+                gene = GeneType::D;
+            }
             let mut start = 4;
             let number = if s.len() > 4 && &s[4..5] == "(" {
                 let end = s[5..].find(')').ok_or(format!(
@@ -379,6 +546,12 @@ impl Gene {
                 family.push(branch);
                 tail = t.trim_start_matches('-');
             }
+            // Genes shared between TRA and TRD have a secondary name, eg `TRAV14/DV4`
+            if let Some(secondary) = tail.strip_prefix('/') {
+                let end = secondary.find('*').unwrap_or(secondary.len());
+                family.push((None, format!("/{}", &secondary[..end])));
+                tail = &secondary[end..];
+            }
 
             Ok((
                 Self {
@@ -390,7 +563,7 @@ impl Gene {
                 tail,
             ))
         } else {
-            Err("Gene name does not start with IG")?
+            Err("Gene name does not start with IG or TR")?
         }
     }
 }
@@ -406,6 +579,30 @@ pub enum ChainType {
     LightLambda,
     /// Fish I kind
     Iota,
+    /// T cell receptor alpha chain
+    Alpha,
+    /// T cell receptor beta chain
+    Beta,
+    /// T cell receptor gamma chain
+    Gamma,
+    /// T cell receptor delta chain
+    Delta,
+}
+
+impl ChainType {
+    /// Check if this chain is a T cell receptor chain (TRA/TRB/TRG/TRD)
+    pub const fn is_t_cell_receptor(self) -> bool {
+        matches!(self, Self::Alpha | Self::Beta | Self::Gamma | Self::Delta)
+    }
+
+    /// The IMGT locus prefix for this chain, `IG` for immunoglobulins or `TR` for T cell receptors
+    pub const fn prefix(self) -> &'static str {
+        if self.is_t_cell_receptor() {
+            "TR"
+        } else {
+            "IG"
+        }
+    }
 }
 
 impl TryFrom<usize> for ChainType {
@@ -416,6 +613,10 @@ impl TryFrom<usize> for ChainType {
             1 => Ok(Self::LightKappa),
             2 => Ok(Self::LightLambda),
             3 => Ok(Self::Iota),
+            4 => Ok(Self::Alpha),
+            5 => Ok(Self::Beta),
+            6 => Ok(Self::Gamma),
+            7 => Ok(Self::Delta),
             _ => Err(()),
         }
     }
@@ -429,6 +630,10 @@ impl FromStr for ChainType {
             "κ" | "K" => Ok(Self::LightKappa),
             "λ" | "L" => Ok(Self::LightLambda),
             "ι" | "I" => Ok(Self::Iota),
+            "α" | "A" => Ok(Self::Alpha),
+            "β" | "B" => Ok(Self::Beta),
+            "γ" | "G" => Ok(Self::Gamma),
+            "δ" | "D" => Ok(Self::Delta),
             _ => Err(()),
         }
     }
@@ -444,6 +649,10 @@ impl Display for ChainType {
                 Self::LightKappa => "K",
                 Self::LightLambda => "L",
                 Self::Iota => "I",
+                Self::Alpha => "A",
+                Self::Beta => "B",
+                Self::Gamma => "G",
+                Self::Delta => "D",
             }
         )
     }
@@ -454,6 +663,8 @@ impl Display for ChainType {
 pub enum GeneType {
     /// Variable
     V,
+    /// Diversity
+    D,
     /// Joining
     J,
     /// Constant, potentially with the type of constant given as well
@@ -502,10 +713,10 @@ impl Display for GeneType {
             "{}",
             match self {
                 Self::V => "V",
+                Self::D | Self::C(Some(Constant::D)) => "D",
                 Self::J => "J",
                 Self::C(None) => "C",
                 Self::C(Some(Constant::A)) => "A",
-                Self::C(Some(Constant::D)) => "D",
                 Self::C(Some(Constant::E)) => "E",
                 Self::C(Some(Constant::G)) => "G",
                 Self::C(Some(Constant::M)) => "M",
@@ -518,6 +729,110 @@ impl Display for GeneType {
     }
 }
 
+/// The accessions in LIGM-DB that support a germline sequence
+#[derive(Clone, Serialize, Deserialize, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Provenance {
+    /// All accession numbers that contain this exact amino acid sequence for this allele
+    pub accessions: Vec<String>,
+    /// The number of accessions that agree on the chosen regions
+    pub regions_support: usize,
+    /// The number of accessions that agree on the chosen annotations
+    pub annotations_support: usize,
+    /// The label of this allele in the source germline set, only set for sources that do not use IMGT names (eg AIRR-C germline sets)
+    pub label: Option<String>,
+}
+
+impl Provenance {
+    /// The number of accessions that agree on the sequence, this is all accessions
+    pub fn sequence_support(&self) -> usize {
+        self.accessions.len()
+    }
+}
+
+/// The ends of a partial gene that are missing from the sequence
+#[derive(Clone, Copy, Serialize, Deserialize, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Truncation {
+    /// The 5' end (N terminal side) of the gene is missing
+    pub five_prime: bool,
+    /// The 3' end (C terminal side) of the gene is missing
+    pub three_prime: bool,
+}
+
+impl Truncation {
+    /// If either end of the gene is missing
+    pub const fn is_partial(self) -> bool {
+        self.five_prime || self.three_prime
+    }
+}
+
+impl Display for Truncation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match (self.five_prime, self.three_prime) {
+                (false, false) => "complete",
+                (true, false) => "5' truncated",
+                (false, true) => "3' truncated",
+                (true, true) => "5' and 3' truncated",
+            }
+        )
+    }
+}
+
+/// The functionality of an allele, as defined by IMGT
+#[derive(Debug, Serialize, Deserialize, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Functionality {
+    /// Functional (F), has an open reading frame, no defect in splicing sites, recombination signals, or regulatory elements
+    Functional,
+    /// Open reading frame (ORF), has an open reading frame but alterations in splicing sites, recombination signals, or regulatory elements
+    OpenReadingFrame,
+    /// In-frame pseudogene (P), has a defect (eg a stop codon) but keeps the reading frame
+    InFramePseudogene,
+    /// Pseudogene (P), has a frameshift or other defect in the coding region
+    Pseudogene,
+    /// Not annotated in the source, eg a non functional allele in an AIRR germline set
+    Unknown,
+}
+
+impl Display for Functionality {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                Self::Functional => "F",
+                Self::OpenReadingFrame => "ORF",
+                Self::InFramePseudogene => "in-frame P",
+                Self::Pseudogene => "P",
+                Self::Unknown => "unknown",
+            }
+        )
+    }
+}
+
+/// The isoform of a constant gene
+#[derive(Debug, Serialize, Deserialize, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Isoform {
+    /// The secreted form, ending in the secretory tail (CHS)
+    Secreted,
+    /// The membrane bound form, ending in the membrane exons (M/M1/M2)
+    Membrane,
+}
+
+impl Display for Isoform {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                Self::Secreted => "secreted",
+                Self::Membrane => "membrane",
+            }
+        )
+    }
+}
+
 /// Any region in a germline, eg FR1, CDR1
 #[derive(Debug, Serialize, Deserialize, Copy, Clone, PartialEq, Eq, Hash)]
 pub enum Region {
@@ -529,7 +844,9 @@ pub enum Region {
     FR3,
     FR4,
     CH1,
-    H,
+    /// A hinge exon, numbered from 1 (eg `IgG3` and `IgD` have multiple hinge exons), with the part of the
+    /// hinge if the core hinge could be determined
+    Hinge(usize, Option<HingePart>),
     H_CH2,
     CH2,
     CH3,
@@ -552,45 +869,140 @@ pub enum Region {
     M,
     M1,
     M2,
+    /// The leader (signal) peptide of a V gene, this is stored separately from the mature sequence
+    Leader,
 }
 
 impl Display for Region {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Self::CDR1 => "CDR1",
+            Self::CDR2 => "CDR2",
+            Self::CDR3 => "CDR3",
+            Self::FR1 => "FR1",
+            Self::FR2 => "FR2",
+            Self::FR3 => "FR3",
+            Self::FR4 => "FR4",
+            Self::CH1 => "CH1",
+            Self::Hinge(..) => "H",
+            Self::H_CH2 => "H-CHS",
+            Self::CH2 => "CH2",
+            Self::CH3 => "CH3",
+            Self::CH4 => "CH4",
+            Self::CH5 => "CH5",
+            Self::CH6 => "CH6",
+            Self::CH7 => "CH7",
+            Self::CH8 => "CH8",
+            Self::CH9 => "CH9",
+            Self::CH2_CHS => "CH2-CHS",
+            Self::CH3_CHS => "CH3-CHS",
+            Self::CH4_CHS => "CH4-CHS",
+            Self::CH5_CHS => "CH5-CHS",
+            Self::CH6_CHS => "CH6-CHS",
+            Self::CH7_CHS => "CH7-CHS",
+            Self::CH8_CHS => "CH8-CHS",
+            Self::CH9_CHS => "CH9-CHS",
+            Self::CHS => "CHS",
+            Self::CL => "CL",
+            Self::M => "M",
+            Self::M1 => "M1",
+            Self::M2 => "M2",
+            Self::Leader => "Leader",
+        };
+        match self {
+            Self::Hinge(exon, Some(part)) => write!(f, "{name}{exon}-{part}"),
+            Self::Hinge(exon, None) => write!(f, "{name}{exon}"),
+            _ => write!(f, "{name}"),
+        }
+    }
+}
+
+impl FromStr for Region {
+    type Err = ();
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "CDR1" => Ok(Self::CDR1),
+            "CDR2" => Ok(Self::CDR2),
+            "CDR3" => Ok(Self::CDR3),
+            "FR1" => Ok(Self::FR1),
+            "FR2" => Ok(Self::FR2),
+            "FR3" => Ok(Self::FR3),
+            "FR4" => Ok(Self::FR4),
+            "CH1" => Ok(Self::CH1),
+            "H" => Ok(Self::Hinge(1, None)),
+            "H-CHS" => Ok(Self::H_CH2),
+            "CH2" => Ok(Self::CH2),
+            "CH3" => Ok(Self::CH3),
+            "CH4" => Ok(Self::CH4),
+            "CH5" => Ok(Self::CH5),
+            "CH6" => Ok(Self::CH6),
+            "CH7" => Ok(Self::CH7),
+            "CH8" => Ok(Self::CH8),
+            "CH9" => Ok(Self::CH9),
+            "CH2-CHS" => Ok(Self::CH2_CHS),
+            "CH3-CHS" => Ok(Self::CH3_CHS),
+            "CH4-CHS" => Ok(Self::CH4_CHS),
+            "CH5-CHS" => Ok(Self::CH5_CHS),
+            "CH6-CHS" => Ok(Self::CH6_CHS),
+            "CH7-CHS" => Ok(Self::CH7_CHS),
+            "CH8-CHS" => Ok(Self::CH8_CHS),
+            "CH9-CHS" => Ok(Self::CH9_CHS),
+            "CHS" => Ok(Self::CHS),
+            "CL" => Ok(Self::CL),
+            "M" => Ok(Self::M),
+            "M1" => Ok(Self::M1),
+            "M2" => Ok(Self::M2),
+            "Leader" | "L" => Ok(Self::Leader),
+            _ => {
+                let hinge = s.strip_prefix('H').ok_or(())?;
+                let (exon, part) = hinge
+                    .split_once('-')
+                    .map_or((hinge, None), |(exon, part)| (exon, Some(part)));
+                Ok(Self::Hinge(
+                    exon.parse().map_err(|_| ())?,
+                    part.map(str::parse).transpose()?,
+                ))
+            }
+        }
+    }
+}
+
+/// The part of the hinge, the core hinge contains the inter heavy chain disulfide bridges (the C-P-x-C motifs),
+/// the upper hinge precedes it and the lower hinge follows it.
+///
+/// Only the residues in the hinge exons are split, for `IgG` the lower hinge is encoded at the start of the
+/// CH2 exon and is part of [`Region::CH2`].
+#[derive(Debug, Serialize, Deserialize, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum HingePart {
+    /// The upper hinge, between the CH1 domain and the core hinge
+    Upper,
+    /// The core (middle) hinge, with the inter heavy chain disulfide bridges
+    Core,
+    /// The lower hinge, between the core hinge and the CH2 domain, only the residues in the hinge exons
+    Lower,
+}
+
+impl FromStr for HingePart {
+    type Err = ();
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "upper" => Ok(Self::Upper),
+            "core" => Ok(Self::Core),
+            "lower" => Ok(Self::Lower),
+            _ => Err(()),
+        }
+    }
+}
+
+impl Display for HingePart {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(
             f,
             "{}",
             match self {
-                Self::CDR1 => "CDR1",
-                Self::CDR2 => "CDR2",
-                Self::CDR3 => "CDR3",
-                Self::FR1 => "FR1",
-                Self::FR2 => "FR2",
-                Self::FR3 => "FR3",
-                Self::FR4 => "FR4",
-                Self::CH1 => "CH1",
-                Self::H => "H",
-                Self::H_CH2 => "H-CHS",
-                Self::CH2 => "CH2",
-                Self::CH3 => "CH3",
-                Self::CH4 => "CH4",
-                Self::CH5 => "CH5",
-                Self::CH6 => "CH6",
-                Self::CH7 => "CH7",
-                Self::CH8 => "CH8",
-                Self::CH9 => "CH9",
-                Self::CH2_CHS => "CH2-CHS",
-                Self::CH3_CHS => "CH3-CHS",
-                Self::CH4_CHS => "CH4-CHS",
-                Self::CH5_CHS => "CH5-CHS",
-                Self::CH6_CHS => "CH6-CHS",
-                Self::CH7_CHS => "CH7-CHS",
-                Self::CH8_CHS => "CH8-CHS",
-                Self::CH9_CHS => "CH9-CHS",
-                Self::CHS => "CHS",
-                Self::CL => "CL",
-                Self::M => "M",
-                Self::M1 => "M1",
-                Self::M2 => "M2",
+                Self::Upper => "upper",
+                Self::Core => "core",
+                Self::Lower => "lower",
             }
         )
     }
@@ -609,24 +1021,57 @@ pub enum Annotation {
     Phenylalanine,
     /// Any of the conserved glycines of the J motif
     Glycine,
-    /// A potential N linked glycan position
+    /// A potential N linked glycan position, an N-x-S/T sequon where x is not a proline
     NGlycan,
+    /// A potential N linked glycan position on an atypical N-x-C sequon where x is not a proline
+    NGlycanAtypical,
+    /// An N linked glycan position that is known to be occupied, eg Asn297 in the CH2 of `IgG`
+    NGlycanOccupied,
+    /// A candidate O linked glycan position, a serine or threonine in the hinge close to a proline
+    OGlycan,
+    /// A residue defining the allotype of a constant allele
+    Allotype(Allotype),
+    /// A cysteine in a disulfide bond, with its partner
+    Disulfide(DisulfidePartner),
+}
+
+/// The partner of a cysteine in a disulfide bond
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, Debug)]
+pub enum DisulfidePartner {
+    /// A bond within the same sequence (eg within a domain), with the index of the partner cysteine
+    Intra(usize),
+    /// An inter chain bond with the light chain, placed on heavy chains
+    LightChain,
+    /// An inter chain bond with a heavy chain, placed on light chains (bonding to the paired heavy chain)
+    /// and on heavy chains (bonding to the other heavy chain)
+    HeavyChain,
+}
+
+impl Display for DisulfidePartner {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Intra(index) => write!(f, "{index}"),
+            Self::LightChain => write!(f, "L"),
+            Self::HeavyChain => write!(f, "H"),
+        }
+    }
 }
 
 impl Display for Annotation {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(
-            f,
-            "{}",
-            match self {
-                Self::Cysteine1 => "Cys1",
-                Self::Cysteine2 => "Cys2",
-                Self::Tryptophan => "Trp",
-                Self::Phenylalanine => "Phe",
-                Self::Glycine => "Gly",
-                Self::NGlycan => "NGly",
-            }
-        )
+        match self {
+            Self::Cysteine1 => write!(f, "Cys1"),
+            Self::Cysteine2 => write!(f, "Cys2"),
+            Self::Tryptophan => write!(f, "Trp"),
+            Self::Phenylalanine => write!(f, "Phe"),
+            Self::Glycine => write!(f, "Gly"),
+            Self::NGlycan => write!(f, "NGly"),
+            Self::NGlycanAtypical => write!(f, "NGlyAtyp"),
+            Self::NGlycanOccupied => write!(f, "NGlyOcc"),
+            Self::OGlycan => write!(f, "OGly"),
+            Self::Allotype(allotype) => write!(f, "{allotype}"),
+            Self::Disulfide(partner) => write!(f, "SS-{partner}"),
+        }
     }
 }
 
@@ -645,4 +1090,76 @@ fn imgt_names() {
             .unwrap(),
         ("IGKV6-d".to_string(), 1)
     );
+    assert_eq!(
+        Gene::from_imgt_name_with_allele("TRBV20-1*02")
+            .map(|(g, a)| (g.to_string(), a))
+            .unwrap(),
+        ("TRBV20-1".to_string(), 2)
+    );
+    assert_eq!(
+        Gene::from_imgt_name_with_allele("TRAV14/DV4*01")
+            .map(|(g, a)| (g.to_string(), a))
+            .unwrap(),
+        ("TRAV14/DV4".to_string(), 1)
+    );
+    assert!(Gene::from_imgt_name("TRHV1").is_err());
+    assert_eq!(
+        Gene::from_imgt_name_with_allele("IGHD3-10*01").map(|(g, a)| (g.gene, a)),
+        Ok((GeneType::D, 1))
+    );
+    assert_eq!(
+        Gene::from_imgt_name_with_allele("IGHD*02").map(|(g, a)| (g.gene, a)),
+        Ok((GeneType::C(Some(Constant::D)), 2))
+    );
+}
+
+#[allow(clippy::missing_panics_doc)]
+#[test]
+fn validation() {
+    let sequence: LinearPeptide = "QCWGC"
+        .bytes()
+        .map(|b| AminoAcid::try_from(b).unwrap())
+        .collect::<Vec<_>>()
+        .into();
+    let dna = "cagtgttggggctgc";
+    assert!(validate(
+        &sequence,
+        &[(Region::FR1, 2), (Region::CDR1, 3)],
+        &[
+            (Annotation::Cysteine1, 1),
+            (Annotation::Tryptophan, 2),
+            (Annotation::Disulfide(DisulfidePartner::Intra(4)), 1),
+            (Annotation::Disulfide(DisulfidePartner::Intra(1)), 4),
+        ],
+        dna,
+        &[(Region::FR1, 6), (Region::CDR1, 9)],
+    )
+    .is_empty());
+    let violations = validate(
+        &sequence,
+        &[(Region::CDR1, 2), (Region::FR1, 2)],
+        &[
+            (Annotation::Tryptophan, 0),
+            (Annotation::Glycine, 7),
+            (Annotation::Disulfide(DisulfidePartner::Intra(3)), 1),
+        ],
+        dna,
+        &[(Region::FR1, 6), (Region::CDR1, 8)],
+    );
+    assert_eq!(violations.len(), 6, "{violations:?}");
+    assert!(violations
+        .contains(&"the regions sum to 4 residues but the sequence has 5 residues".to_string()));
+    assert!(violations.contains(
+        &"the DNA regions sum to 14 nucleotides but the DNA has 15 nucleotides".to_string()
+    ));
+    assert!(violations
+        .iter()
+        .any(|v| v.ends_with("at 0 points at Q instead of W")));
+    assert!(violations
+        .iter()
+        .any(|v| v.ends_with("at 7 is outside of the sequence (5 residues)")));
+    assert!(violations
+        .iter()
+        .any(|v| v.ends_with("at 1 is not paired with a cysteine at 3")));
+    assert!(violations.contains(&"FR1 follows CDR1".to_string()));
 }